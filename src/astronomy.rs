@@ -0,0 +1,264 @@
+use jiff::ToSpan;
+
+use crate::timezone::{Date, TimeZone};
+
+/// Julian day of the Unix epoch
+const UNIX_EPOCH_JULIAN_DAY: f64 = 2_440_587.5;
+/// Julian day of the J2000.0 epoch, 2000-01-01 12:00
+const J2000: f64 = 2_451_545.0;
+/// Altitude of the sun's centre at sunrise and sunset, in degrees:
+/// refraction and the radius of the disc let the upper limb show while
+/// the centre is still below the horizon
+pub const SUN_HORIZON: f64 = -0.833;
+/// Interval at which altitudes are sampled when looking for rise and set,
+/// in seconds; short enough that no rise and set fit in between
+const SCAN_STEP: i64 = 600;
+/// Halvings of a sampling interval when pinning down a crossing, which
+/// brings it well below a second
+const REFINE_STEPS: u32 = 12;
+
+/// Sun times of one local day, in Unix seconds
+#[derive(Debug, Clone, Copy)]
+pub struct SunTimes {
+    /// `None` when the sun stays above or below the horizon all day
+    pub sunrise: Option<i64>,
+    pub sunset: Option<i64>,
+    pub solar_noon: Option<i64>,
+    /// Elevation of the sun's centre at solar noon, in degrees
+    pub noon_elevation: Option<f64>,
+}
+
+/// Moon times of one local day, in Unix seconds
+#[derive(Debug, Clone, Copy)]
+pub struct MoonTimes {
+    /// `None` when the moon does not rise that day, which happens about
+    /// once a month even outside the polar regions
+    pub moonrise: Option<i64>,
+    pub moonset: Option<i64>,
+    /// Phase angle in degrees: 0 at new moon, 90 at first quarter, 180 at
+    /// full moon and 270 at last quarter
+    pub phase: f64,
+}
+
+/// Sunrise, sunset and solar noon on a local calendar day, calculated
+/// from a low-precision solar theory good to about a minute
+pub fn sun_times(latitude: f64, longitude: f64, date: Date, tz: &TimeZone) -> SunTimes {
+    let (start, end) = (tz.timestamp(date, 0), tz.timestamp(date.saturating_add(1.day()), 0));
+    let sun_position = |t| {
+        let d = days_since_j2000(t);
+        equatorial(sun_longitude(d), 0.0, d)
+    };
+
+    let (sunrise, sunset) = crossings(start, end, |t| {
+        let (ra, dec) = sun_position(t);
+        altitude(latitude, longitude, t, ra, dec) - SUN_HORIZON
+    });
+    // The sun culminates where its hour angle passes zero
+    let (solar_noon, _) = crossings(start, end, |t| hour_angle(longitude, t, sun_position(t).0));
+    let noon_elevation = solar_noon.map(|t| {
+        let (ra, dec) = sun_position(t);
+        (altitude(latitude, longitude, t, ra, dec) * 100.0).round() / 100.0
+    });
+
+    SunTimes {
+        sunrise,
+        sunset,
+        solar_noon,
+        noon_elevation,
+    }
+}
+
+/// Moonrise, moonset and the phase at local noon on a local calendar day,
+/// calculated from a low-precision lunar theory good to a few minutes
+pub fn moon_times(latitude: f64, longitude: f64, date: Date, tz: &TimeZone) -> MoonTimes {
+    let (start, end) = (tz.timestamp(date, 0), tz.timestamp(date.saturating_add(1.day()), 0));
+
+    let (moonrise, moonset) = crossings(start, end, |t| {
+        let d = days_since_j2000(t);
+        let (moon_longitude, moon_latitude, parallax) = moon_ecliptic(d);
+        let (ra, dec) = equatorial(moon_longitude, moon_latitude, d);
+        // Parallax lowers the moon by almost a degree; refraction and the
+        // radius of the disc lift it again
+        let horizon = 0.7275 * parallax - 0.5667;
+        altitude(latitude, longitude, t, ra, dec) - horizon
+    });
+
+    let d = days_since_j2000(tz.timestamp(date, 12 * 3600));
+    let phase = (moon_ecliptic(d).0 - sun_longitude(d)).rem_euclid(360.0);
+
+    MoonTimes {
+        moonrise,
+        moonset,
+        phase: (phase * 10.0).round() / 10.0,
+    }
+}
+
+/// First upward and first downward zero crossing of `f` between `start`
+/// and `end`
+fn crossings(start: i64, end: i64, f: impl Fn(i64) -> f64) -> (Option<i64>, Option<i64>) {
+    let (mut rising, mut setting) = (None, None);
+    let mut t = start;
+    let mut value = f(t);
+    while t < end && (rising.is_none() || setting.is_none()) {
+        let next = (t + SCAN_STEP).min(end);
+        let next_value = f(next);
+        if value < 0.0 && next_value >= 0.0 && rising.is_none() {
+            rising = Some(refine(t, next, &f));
+        } else if value >= 0.0 && next_value < 0.0 && setting.is_none() {
+            setting = Some(refine(t, next, &f));
+        }
+        t = next;
+        value = next_value;
+    }
+    (rising, setting)
+}
+
+/// Narrows down where `f` changes sign between `a` and `b` by bisection
+fn refine(mut a: i64, mut b: i64, f: &impl Fn(i64) -> f64) -> i64 {
+    let below = f(a) < 0.0;
+    for _ in 0..REFINE_STEPS {
+        let mid = a + (b - a) / 2;
+        if (f(mid) < 0.0) == below {
+            a = mid;
+        } else {
+            b = mid;
+        }
+    }
+    a + (b - a) / 2
+}
+
+/// Days since J2000.0 of a Unix timestamp; the difference between UTC and
+/// terrestrial time is negligible at this precision
+fn days_since_j2000(timestamp: i64) -> f64 {
+    timestamp as f64 / 86_400.0 + UNIX_EPOCH_JULIAN_DAY - J2000
+}
+
+/// Apparent ecliptic longitude of the sun, in degrees
+fn sun_longitude(d: f64) -> f64 {
+    let mean_anomaly = (357.529 + 0.985_600_28 * d).to_radians();
+    let mean_longitude = 280.459 + 0.985_647_36 * d;
+    mean_longitude + 1.915 * mean_anomaly.sin() + 0.020 * (2.0 * mean_anomaly).sin()
+}
+
+/// Ecliptic longitude and latitude and horizontal parallax of the moon, in
+/// degrees, from the low-precision formulae of the Astronomical Almanac
+fn moon_ecliptic(d: f64) -> (f64, f64, f64) {
+    let t = d / 36_525.0;
+    let sin = |a: f64, b: f64| (a + b * t).to_radians().sin();
+    let cos = |a: f64, b: f64| (a + b * t).to_radians().cos();
+
+    let longitude = 218.32 + 481_267.881 * t + 6.29 * sin(135.0, 477_198.87) - 1.27 * sin(259.3, -413_335.36)
+        + 0.66 * sin(235.7, 890_534.22)
+        + 0.21 * sin(269.9, 954_397.74)
+        - 0.19 * sin(357.5, 35_999.05)
+        - 0.11 * sin(186.5, 966_404.03);
+    let latitude = 5.13 * sin(93.3, 483_202.02) + 0.28 * sin(228.2, 960_400.89)
+        - 0.28 * sin(318.3, 6_003.15)
+        - 0.17 * sin(217.6, -407_332.21);
+    let parallax = 0.9508
+        + 0.0518 * cos(135.0, 477_198.87)
+        + 0.0095 * cos(259.3, -413_335.36)
+        + 0.0078 * cos(235.7, 890_534.22)
+        + 0.0028 * cos(269.9, 954_397.74);
+    (longitude, latitude, parallax)
+}
+
+/// Right ascension and declination, in degrees, of a point given in
+/// ecliptic coordinates
+fn equatorial(longitude: f64, latitude: f64, d: f64) -> (f64, f64) {
+    let obliquity = (23.439 - 0.000_000_36 * d).to_radians();
+    let (longitude, latitude) = (longitude.to_radians(), latitude.to_radians());
+    let right_ascension = (longitude.sin() * obliquity.cos() - latitude.tan() * obliquity.sin()).atan2(longitude.cos());
+    let declination = (latitude.sin() * obliquity.cos() + latitude.cos() * obliquity.sin() * longitude.sin()).asin();
+    (right_ascension.to_degrees(), declination.to_degrees())
+}
+
+/// Local hour angle in degrees, between -180 and 180
+fn hour_angle(longitude: f64, timestamp: i64, right_ascension: f64) -> f64 {
+    let sidereal_time = 280.460_618_37 + 360.985_647_366_29 * days_since_j2000(timestamp);
+    (sidereal_time + longitude - right_ascension + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/// Geocentric altitude above the horizon, in degrees
+fn altitude(latitude: f64, longitude: f64, timestamp: i64, right_ascension: f64, declination: f64) -> f64 {
+    let hour_angle = hour_angle(longitude, timestamp, right_ascension).to_radians();
+    let (latitude, declination) = (latitude.to_radians(), declination.to_radians());
+    (latitude.sin() * declination.sin() + latitude.cos() * declination.cos() * hour_angle.cos())
+        .asin()
+        .to_degrees()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TROMSO: (f64, f64) = (69.6492, 18.9553);
+
+    fn zone(name: &str) -> TimeZone {
+        TimeZone::from_name(name).unwrap()
+    }
+
+    /// Unix seconds of a local wall-clock time
+    fn at(tz: &TimeZone, date: &str, hour: u32, minute: u32) -> i64 {
+        tz.timestamp(date.parse::<Date>().unwrap(), hour * 3600 + minute * 60)
+    }
+
+    fn assert_near(actual: Option<i64>, expected: i64) {
+        let actual = actual.expect("event missing");
+        assert!((actual - expected).abs() <= 120, "off by {} s", actual - expected);
+    }
+
+    #[test]
+    fn london_midsummer_matches_published_times() {
+        let tz = zone("Europe/London");
+        let sun = sun_times(51.5072, -0.1276, "2024-06-20".parse::<Date>().unwrap(), &tz);
+        assert_near(sun.sunrise, at(&tz, "2024-06-20", 4, 43));
+        assert_near(sun.sunset, at(&tz, "2024-06-20", 21, 21));
+        assert_near(sun.solar_noon, at(&tz, "2024-06-20", 13, 2));
+        assert!((sun.noon_elevation.unwrap() - 61.9).abs() < 0.2);
+    }
+
+    #[test]
+    fn new_york_midwinter_matches_published_times() {
+        let tz = zone("America/New_York");
+        let sun = sun_times(40.7128, -74.0060, "2024-12-21".parse::<Date>().unwrap(), &tz);
+        assert_near(sun.sunrise, at(&tz, "2024-12-21", 7, 16));
+        assert_near(sun.sunset, at(&tz, "2024-12-21", 16, 32));
+    }
+
+    #[test]
+    fn tromso_has_midnight_sun_in_june() {
+        let tz = zone("Europe/Oslo");
+        let sun = sun_times(TROMSO.0, TROMSO.1, "2024-06-21".parse::<Date>().unwrap(), &tz);
+        assert_eq!((sun.sunrise, sun.sunset), (None, None));
+        assert!(sun.solar_noon.is_some());
+        assert!(sun.noon_elevation.unwrap() > SUN_HORIZON);
+    }
+
+    #[test]
+    fn tromso_has_polar_night_in_december() {
+        let tz = zone("Europe/Oslo");
+        let sun = sun_times(TROMSO.0, TROMSO.1, "2024-12-21".parse::<Date>().unwrap(), &tz);
+        assert_eq!((sun.sunrise, sun.sunset), (None, None));
+        assert!(sun.noon_elevation.unwrap() < SUN_HORIZON);
+    }
+
+    #[test]
+    fn moon_phase_follows_the_lunation() {
+        let tz = TimeZone::utc();
+        // Full moon 2024-06-22 01:08 UTC, new moon 2024-07-05 22:57 UTC
+        let full = moon_times(0.0, 0.0, "2024-06-22".parse::<Date>().unwrap(), &tz).phase;
+        assert!((full - 180.0).abs() < 10.0, "phase {}", full);
+        let new = moon_times(0.0, 0.0, "2024-07-05".parse::<Date>().unwrap(), &tz).phase;
+        assert!(new > 350.0, "phase {}", new);
+    }
+
+    #[test]
+    fn moon_rises_and_sets_at_mid_latitudes() {
+        let tz = zone("Europe/London");
+        let moon = moon_times(51.5072, -0.1276, "2024-06-20".parse::<Date>().unwrap(), &tz);
+        // Waxing gibbous: rises in the evening and sets in the early morning
+        assert_near(moon.moonrise, at(&tz, "2024-06-20", 20, 34));
+        assert_near(moon.moonset, at(&tz, "2024-06-20", 2, 50));
+    }
+}
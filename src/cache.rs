@@ -0,0 +1,384 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tokio::fs;
+use tracing::{debug, warn};
+
+/// Most responses kept in memory before the soonest-expiring one is dropped
+const DEFAULT_MAX_ENTRIES: usize = 512;
+/// Extension of cached response bodies
+const BODY_EXTENSION: &str = "json";
+/// Extension of the metadata stored next to each body
+const META_EXTENSION: &str = "meta";
+/// Subdirectory of the cache directory holding the entries; the cache
+/// never touches anything outside it
+const ENTRIES_DIR: &str = "responses";
+
+/// A met.no response body together with the headers needed to reuse it
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    pub body: String,
+    /// Value of the `Expires` header; the body may be served without
+    /// contacting met.no until then
+    pub expires: SystemTime,
+    /// Value of the `Last-Modified` header, echoed as `If-Modified-Since`
+    /// when revalidating
+    pub last_modified: Option<String>,
+}
+
+impl CachedResponse {
+    /// Whether the response can still be used without revalidation
+    pub fn is_fresh(&self) -> bool {
+        SystemTime::now() < self.expires
+    }
+}
+
+/// Cache of met.no responses keyed by request URL.
+///
+/// Request URLs carry coordinates rounded to four decimals, so nearby
+/// lookups for the same spot share an entry. Entries live in memory and,
+/// when a [`DiskCache`] is attached, on disk so they survive restarts.
+pub struct ResponseCache {
+    entries: Mutex<HashMap<String, CachedResponse>>,
+    max_entries: usize,
+    disk: Option<DiskCache>,
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            max_entries: DEFAULT_MAX_ENTRIES,
+            disk: None,
+        }
+    }
+}
+
+impl ResponseCache {
+    /// Creates a cache that also persists entries to `disk`
+    pub fn with_disk(disk: DiskCache) -> Self {
+        Self {
+            disk: Some(disk),
+            ..Self::default()
+        }
+    }
+
+    /// Returns the cached response for `key`, fresh or not
+    pub async fn get(&self, key: &str) -> Option<CachedResponse> {
+        if let Some(cached) = self.entries.lock().unwrap().get(key).cloned() {
+            return Some(cached);
+        }
+
+        let cached = self.disk.as_ref()?.get(key).await?;
+        debug!("Loaded {} from disk cache", key);
+        self.insert_in_memory(key.to_string(), cached.clone());
+        Some(cached)
+    }
+
+    /// Stores a response in memory and on disk
+    pub async fn insert(&self, key: String, response: CachedResponse) {
+        if let Some(disk) = &self.disk {
+            disk.insert(&key, &response).await;
+        }
+        self.insert_in_memory(key, response);
+    }
+
+    /// Stores a response in memory, evicting the entry closest to expiry
+    /// when full
+    fn insert_in_memory(&self, key: String, response: CachedResponse) {
+        let mut entries = self.entries.lock().unwrap();
+
+        if entries.len() >= self.max_entries && !entries.contains_key(&key) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, cached)| cached.expires)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+
+        entries.insert(key, response);
+    }
+}
+
+/// Freshness metadata stored next to a cached body
+#[derive(Debug, Serialize, Deserialize)]
+struct DiskMeta {
+    url: String,
+    /// HTTP date, as sent in the `Expires` header
+    expires: String,
+    last_modified: Option<String>,
+}
+
+/// Directory of raw met.no payloads, each stored as `<name>.json` with its
+/// `Expires`/`Last-Modified` metadata in `<name>.meta`. Entries live in a
+/// `responses` subdirectory so the cache directory may be shared with other
+/// files.
+///
+/// Disk problems are logged and otherwise ignored: the cache only ever saves
+/// requests, it is never needed for correctness.
+pub struct DiskCache {
+    dir: PathBuf,
+    max_bytes: u64,
+}
+
+impl DiskCache {
+    /// Opens (creating if needed) a cache directory holding at most
+    /// `max_bytes` of entries
+    pub fn open(dir: PathBuf, max_bytes: u64) -> io::Result<Self> {
+        let dir = dir.join(ENTRIES_DIR);
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir, max_bytes })
+    }
+
+    async fn get(&self, key: &str) -> Option<CachedResponse> {
+        let name = file_name(key);
+        let meta = fs::read_to_string(self.path(&name, META_EXTENSION)).await.ok()?;
+        let meta: DiskMeta = match serde_json::from_str(&meta) {
+            Ok(meta) => meta,
+            Err(e) => {
+                warn!("Ignoring corrupt disk cache entry {}: {}", name, e);
+                return None;
+            }
+        };
+        // Guard against two URLs mapping to the same file name
+        if meta.url != key {
+            return None;
+        }
+
+        let body = fs::read_to_string(self.path(&name, BODY_EXTENSION)).await.ok()?;
+        Some(CachedResponse {
+            body,
+            expires: httpdate::parse_http_date(&meta.expires).unwrap_or(SystemTime::UNIX_EPOCH),
+            last_modified: meta.last_modified,
+        })
+    }
+
+    async fn insert(&self, key: &str, response: &CachedResponse) {
+        let name = file_name(key);
+        let meta = DiskMeta {
+            url: key.to_string(),
+            expires: httpdate::fmt_http_date(response.expires),
+            last_modified: response.last_modified.clone(),
+        };
+
+        // The body goes first so a readable .meta always has a complete body
+        let result = async {
+            write_atomically(&self.path(&name, BODY_EXTENSION), response.body.as_bytes()).await?;
+            let meta = serde_json::to_vec(&meta).map_err(io::Error::other)?;
+            write_atomically(&self.path(&name, META_EXTENSION), &meta).await
+        }
+        .await;
+
+        match result {
+            Ok(()) => {
+                if let Err(e) = self.evict().await {
+                    warn!("Failed to trim disk cache {}: {}", self.dir.display(), e);
+                }
+            }
+            Err(e) => warn!("Failed to write disk cache entry {}: {}", name, e),
+        }
+    }
+
+    /// Deletes the least recently written entries until they fit within
+    /// `max_bytes`
+    async fn evict(&self) -> io::Result<()> {
+        let mut entries: HashMap<String, (u64, SystemTime)> = HashMap::new();
+        let mut total = 0;
+
+        let mut dir = fs::read_dir(&self.dir).await?;
+        while let Some(file) = dir.next_entry().await? {
+            let path = file.path();
+            // Only bodies and metadata count; this also skips temporary files
+            // of writes still in progress
+            if !path
+                .extension()
+                .is_some_and(|e| e == BODY_EXTENSION || e == META_EXTENSION)
+            {
+                continue;
+            }
+            let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let metadata = file.metadata().await?;
+            if !metadata.is_file() {
+                continue;
+            }
+
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            let entry = entries.entry(stem.to_string()).or_insert((0, modified));
+            entry.0 += metadata.len();
+            entry.1 = entry.1.max(modified);
+            total += metadata.len();
+        }
+
+        if total <= self.max_bytes {
+            return Ok(());
+        }
+
+        let mut entries: Vec<_> = entries.into_iter().collect();
+        entries.sort_by_key(|(_, (_, modified))| *modified);
+
+        for (name, (size, _)) in entries {
+            if total <= self.max_bytes {
+                break;
+            }
+            debug!("Evicting {} from disk cache", name);
+            for extension in [BODY_EXTENSION, META_EXTENSION] {
+                let _ = fs::remove_file(self.path(&name, extension)).await;
+            }
+            total = total.saturating_sub(size);
+        }
+
+        Ok(())
+    }
+
+    fn path(&self, name: &str, extension: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", name, extension))
+    }
+}
+
+/// Turns a request URL into a readable, filesystem-safe file name
+fn file_name(key: &str) -> String {
+    let key = key.split_once("://").map_or(key, |(_, rest)| rest);
+    key.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect()
+}
+
+/// Writes `contents` to a temporary file and renames it into place so
+/// readers never see a partial file
+async fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+    let tmp = path.with_extension(format!("{}.tmp", extension));
+    fs::write(&tmp, contents).await?;
+    fs::rename(&tmp, path).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// A fresh directory under the system temp dir, removed on drop
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "yr-weather-mcp-{}-{}",
+                name,
+                std::process::id()
+            ));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    const URL: &str =
+        "https://api.met.no/weatherapi/locationforecast/2.0/compact?lat=59.9100&lon=10.7500";
+
+    fn response(body: &str, expires_in: i64) -> CachedResponse {
+        let now = SystemTime::now();
+        let offset = Duration::from_secs(expires_in.unsigned_abs());
+        CachedResponse {
+            body: body.to_string(),
+            expires: if expires_in < 0 {
+                now - offset
+            } else {
+                now + offset
+            },
+            last_modified: Some("Sat, 04 May 2024 10:00:00 GMT".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn disk_entries_survive_a_restart() {
+        let dir = TempDir::new("round-trip");
+        let cache = ResponseCache::with_disk(DiskCache::open(dir.0.clone(), 1 << 20).unwrap());
+        cache
+            .insert(URL.to_string(), response("{\"a\": 1}", 3600))
+            .await;
+
+        // A new cache on the same directory starts with an empty memory
+        let reopened = ResponseCache::with_disk(DiskCache::open(dir.0.clone(), 1 << 20).unwrap());
+        let cached = reopened.get(URL).await.unwrap();
+        assert_eq!(cached.body, "{\"a\": 1}");
+        assert_eq!(
+            cached.last_modified.as_deref(),
+            Some("Sat, 04 May 2024 10:00:00 GMT")
+        );
+        assert!(cached.is_fresh());
+        assert!(reopened.get("https://api.met.no/other").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn keeps_expired_entries_for_revalidation() {
+        let dir = TempDir::new("expiry");
+        let cache = ResponseCache::with_disk(DiskCache::open(dir.0.clone(), 1 << 20).unwrap());
+        cache.insert(URL.to_string(), response("{}", -60)).await;
+
+        let cached = cache.get(URL).await.unwrap();
+        assert!(!cached.is_fresh());
+        let reopened = DiskCache::open(dir.0.clone(), 1 << 20).unwrap();
+        assert!(!reopened.get(URL).await.unwrap().is_fresh());
+    }
+
+    #[tokio::test]
+    async fn ignores_an_entry_stored_for_a_colliding_url() {
+        let dir = TempDir::new("collision");
+        let disk = DiskCache::open(dir.0.clone(), 1 << 20).unwrap();
+        let other = URL.replace('?', "&");
+        assert_eq!(file_name(URL), file_name(&other));
+
+        disk.insert(URL, &response("{}", 3600)).await;
+        assert!(disk.get(&other).await.is_none());
+        assert!(disk.get(URL).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn evicts_the_oldest_entries_and_nothing_else() {
+        let dir = TempDir::new("eviction");
+        let foreign = dir.0.join("package.json");
+        std::fs::write(&foreign, vec![b' '; 10_000]).unwrap();
+
+        let body = "x".repeat(1000);
+        let disk = DiskCache::open(dir.0.clone(), 3500).unwrap();
+        let stray = disk.dir.join("notes.txt");
+        std::fs::write(&stray, vec![b' '; 10_000]).unwrap();
+        for n in 0..5 {
+            disk.insert(&format!("{}&n={}", URL, n), &response(&body, 3600))
+                .await;
+            // Distinct modification times keep the eviction order certain
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        for n in 0..3 {
+            assert!(
+                disk.get(&format!("{}&n={}", URL, n)).await.is_none(),
+                "entry {}",
+                n
+            );
+        }
+        for n in 3..5 {
+            assert!(
+                disk.get(&format!("{}&n={}", URL, n)).await.is_some(),
+                "entry {}",
+                n
+            );
+        }
+        assert!(foreign.exists());
+        assert!(stray.exists());
+    }
+}
@@ -0,0 +1,190 @@
+use serde::Serialize;
+
+use crate::units::beaufort;
+
+/// Wind chill only applies at or below this temperature, in °C
+const WIND_CHILL_MAX_TEMPERATURE: f64 = 10.0;
+/// Wind chill only applies above this wind speed, in m/s (4.8 km/h)
+const WIND_CHILL_MIN_WIND_SPEED: f64 = 4.8 / 3.6;
+/// Heat index and humidex only apply at or above this temperature, in °C
+const HEAT_MIN_TEMPERATURE: f64 = 27.0;
+
+/// How the weather feels, derived from temperature, humidity and wind
+#[derive(Debug, Clone, Serialize)]
+pub struct Comfort {
+    /// Apparent temperature: the wind chill when it applies, else the heat
+    /// index when it applies, else the air temperature
+    pub feels_like: f64,
+    /// Which index `feels_like` comes from
+    pub feels_like_source: FeelsLikeSource,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wind_chill: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heat_index: Option<f64>,
+    /// Canadian humidex, a unitless number on the temperature scale
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub humidex: Option<f64>,
+    /// From met.no when it provides one, otherwise computed from
+    /// temperature and humidity
+    pub dew_point: f64,
+    /// Wind force on the Beaufort scale, 0–12
+    pub beaufort: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeelsLikeSource {
+    AirTemperature,
+    WindChill,
+    HeatIndex,
+}
+
+impl Comfort {
+    /// Derives the indices from air temperature (°C), relative humidity
+    /// (%) and wind speed (m/s); `dew_point` is met.no's value, if any
+    pub fn new(temperature: f64, humidity: f64, wind_speed: f64, dew_point: Option<f64>) -> Self {
+        let dew_point = dew_point.unwrap_or_else(|| dew_point_from_humidity(temperature, humidity));
+        let wind_chill = wind_chill(temperature, wind_speed);
+        let heat_index = heat_index(temperature, humidity);
+        let (feels_like, feels_like_source) = match (wind_chill, heat_index) {
+            (Some(wind_chill), _) => (wind_chill, FeelsLikeSource::WindChill),
+            (None, Some(heat_index)) => (heat_index, FeelsLikeSource::HeatIndex),
+            (None, None) => (temperature, FeelsLikeSource::AirTemperature),
+        };
+
+        Self {
+            feels_like,
+            feels_like_source,
+            wind_chill,
+            heat_index,
+            humidex: humidex(temperature, dew_point),
+            dew_point,
+            beaufort: beaufort(wind_speed),
+        }
+    }
+}
+
+/// Wind chill in °C with the formula used by Environment Canada and the
+/// US National Weather Service
+fn wind_chill(temperature: f64, wind_speed: f64) -> Option<f64> {
+    if temperature > WIND_CHILL_MAX_TEMPERATURE || wind_speed <= WIND_CHILL_MIN_WIND_SPEED {
+        return None;
+    }
+    let v = (wind_speed * 3.6).powf(0.16);
+    Some(round(13.12 + 0.6215 * temperature - 11.37 * v + 0.3965 * temperature * v))
+}
+
+/// Heat index in °C with the US National Weather Service regression,
+/// including its corrections for very dry and very humid air
+fn heat_index(temperature: f64, humidity: f64) -> Option<f64> {
+    if temperature < HEAT_MIN_TEMPERATURE {
+        return None;
+    }
+    let t = temperature * 9.0 / 5.0 + 32.0;
+    let rh = humidity;
+
+    let simple = 0.5 * (t + 61.0 + (t - 68.0) * 1.2 + rh * 0.094);
+    let fahrenheit = if (simple + t) / 2.0 < 80.0 {
+        simple
+    } else {
+        let mut hi = -42.379 + 2.049_015_23 * t + 10.143_331_27 * rh
+            - 0.224_755_41 * t * rh
+            - 0.006_837_83 * t * t
+            - 0.054_817_17 * rh * rh
+            + 0.001_228_74 * t * t * rh
+            + 0.000_852_82 * t * rh * rh
+            - 0.000_001_99 * t * t * rh * rh;
+        if rh < 13.0 && (80.0..=112.0).contains(&t) {
+            hi -= (13.0 - rh) / 4.0 * ((17.0 - (t - 95.0).abs()) / 17.0).sqrt();
+        } else if rh > 85.0 && (80.0..=87.0).contains(&t) {
+            hi += (rh - 85.0) / 10.0 * ((87.0 - t) / 5.0);
+        }
+        hi
+    };
+    Some(round((fahrenheit - 32.0) * 5.0 / 9.0))
+}
+
+/// Humidex from air temperature and dew point, both in °C
+fn humidex(temperature: f64, dew_point: f64) -> Option<f64> {
+    if temperature < HEAT_MIN_TEMPERATURE {
+        return None;
+    }
+    let vapour_pressure = 6.11 * (5417.7530 * (1.0 / 273.16 - 1.0 / (273.15 + dew_point))).exp();
+    Some(round(temperature + 0.5555 * (vapour_pressure - 10.0)))
+}
+
+/// Dew point in °C with the Magnus formula
+fn dew_point_from_humidity(temperature: f64, humidity: f64) -> f64 {
+    const B: f64 = 17.62;
+    const C: f64 = 243.12;
+    // Fully dry air has no dew point; clamp to keep the logarithm finite
+    let gamma = (humidity.max(1.0) / 100.0).ln() + B * temperature / (C + temperature);
+    round(C * gamma / (B - gamma))
+}
+
+/// Rounds to one decimal, the precision of met.no's temperatures
+fn round(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wind_chill_matches_environment_canada_table() {
+        // -10°C in a 20 km/h wind feels like -17.9°C
+        assert_eq!(wind_chill(-10.0, 20.0 / 3.6), Some(-17.9));
+        // -30°C in a 50 km/h wind feels like -49°C
+        assert_eq!(wind_chill(-30.0, 50.0 / 3.6), Some(-49.0));
+    }
+
+    #[test]
+    fn wind_chill_only_applies_when_cold_and_windy() {
+        assert_eq!(wind_chill(10.1, 10.0), None);
+        assert!(wind_chill(10.0, 10.0).is_some());
+        assert_eq!(wind_chill(-5.0, 4.8 / 3.6), None);
+        assert!(wind_chill(-5.0, 1.4).is_some());
+    }
+
+    #[test]
+    fn heat_index_matches_nws_table() {
+        // 90°F at 70% relative humidity is 106°F
+        assert_eq!(heat_index(32.0 + 2.0 / 9.0, 70.0), Some(41.1));
+        // 100°F at 40% is 109°F
+        assert_eq!(heat_index(37.0 + 7.0 / 9.0, 40.0), Some(42.9));
+        assert_eq!(heat_index(26.9, 90.0), None);
+    }
+
+    #[test]
+    fn humidex_matches_environment_canada_table() {
+        // 30°C with a 15°C dew point has a humidex of 34
+        assert_eq!(humidex(30.0, 15.0), Some(34.0));
+        assert_eq!(humidex(26.9, 20.0), None);
+    }
+
+    #[test]
+    fn dew_point_from_relative_humidity() {
+        assert_eq!(dew_point_from_humidity(20.0, 50.0), 9.3);
+        assert_eq!(dew_point_from_humidity(15.0, 100.0), 15.0);
+        assert!(dew_point_from_humidity(30.0, 0.0).is_finite());
+    }
+
+    #[test]
+    fn feels_like_picks_the_applicable_index() {
+        let cold = Comfort::new(-10.0, 80.0, 20.0 / 3.6, None);
+        assert_eq!(cold.feels_like_source, FeelsLikeSource::WindChill);
+        assert_eq!(cold.feels_like, -17.9);
+
+        let hot = Comfort::new(32.0 + 2.0 / 9.0, 70.0, 2.0, None);
+        assert_eq!(hot.feels_like_source, FeelsLikeSource::HeatIndex);
+        assert_eq!(hot.feels_like, 41.1);
+        assert!(hot.humidex.is_some());
+
+        let mild = Comfort::new(18.0, 60.0, 3.0, Some(10.2));
+        assert_eq!(mild.feels_like_source, FeelsLikeSource::AirTemperature);
+        assert_eq!(mild.feels_like, 18.0);
+        assert_eq!(mild.dew_point, 10.2);
+        assert_eq!(mild.beaufort, 2);
+    }
+}
@@ -0,0 +1,97 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::i18n::Language;
+
+/// Settings read from the JSON file given with `--config`.
+///
+/// Every key is optional; command-line flags and their environment variables
+/// take precedence over the file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Contact address (e-mail or URL) added to the User-Agent, as met.no
+    /// requires
+    pub contact: Option<String>,
+    /// Complete User-Agent replacing the generated one
+    pub user_agent: Option<String>,
+    /// Base URL of the met.no API, e.g. to point at a local mock server
+    pub base_url: Option<String>,
+    /// Proxy all met.no requests go through
+    pub proxy: Option<String>,
+    /// PEM file with extra root certificates to trust
+    pub ca_bundle: Option<PathBuf>,
+    /// Language of forecasts when a tool call does not choose one
+    pub language: Option<Language>,
+}
+
+impl Config {
+    /// Reads and parses a config file
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        serde_json::from_str(&contents).with_context(|| format!("Invalid config file {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `contents` to a config file unique to the test
+    fn config_file(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("yr-weather-mcp-{}-{}.json", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn loads_every_setting() {
+        let path = config_file(
+            "full",
+            r#"{
+                "contact": "ops@example.com",
+                "user_agent": "custom/1.0",
+                "base_url": "http://localhost:9000",
+                "proxy": "http://proxy:3128",
+                "ca_bundle": "/etc/ssl/extra.pem",
+                "language": "nb"
+            }"#,
+        );
+        let config = Config::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(config.contact.as_deref(), Some("ops@example.com"));
+        assert_eq!(config.user_agent.as_deref(), Some("custom/1.0"));
+        assert_eq!(config.base_url.as_deref(), Some("http://localhost:9000"));
+        assert_eq!(config.proxy.as_deref(), Some("http://proxy:3128"));
+        assert_eq!(config.ca_bundle, Some(PathBuf::from("/etc/ssl/extra.pem")));
+        assert_eq!(config.language, Some(Language::Nb));
+    }
+
+    #[test]
+    fn every_key_is_optional() {
+        let path = config_file("empty", "{}");
+        let config = Config::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(config.contact.is_none() && config.base_url.is_none() && config.language.is_none());
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_bad_values() {
+        for (name, contents) in [("unknown", r#"{"contcat": "ops@example.com"}"#), ("language", r#"{"language": "de"}"#)] {
+            let path = config_file(name, contents);
+            let error = Config::load(&path).unwrap_err();
+            std::fs::remove_file(&path).unwrap();
+            assert!(format!("{:#}", error).starts_with("Invalid config file"), "{:#}", error);
+        }
+    }
+
+    #[test]
+    fn reports_a_missing_file() {
+        let error = Config::load(Path::new("/nonexistent/yr-weather-mcp.json")).unwrap_err();
+        assert!(error.to_string().starts_with("Failed to read config file"), "{:#}", error);
+    }
+}
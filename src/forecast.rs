@@ -0,0 +1,1038 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::comfort::{Comfort, FeelsLikeSource};
+use crate::i18n::{Language, Messages};
+use crate::sun_moon::{self, SunMoonEvents};
+use crate::symbol::Variant;
+use crate::timezone::{self, TimeZone};
+use crate::units::{PrecipitationUnit, PressureUnit, SpeedUnit, TemperatureUnit, UnitPreset};
+
+/// A forecast for one location, returned to clients both as Markdown text
+/// and as `structuredContent`
+#[derive(Debug, Clone, Serialize)]
+pub struct Forecast {
+    pub location: Location,
+    /// Which view was requested
+    pub forecast_type: ForecastType,
+    /// Language of labels and descriptions
+    pub language: Language,
+    /// When met.no last updated the underlying model run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+    pub units: Units,
+    /// Zone used to split the timeseries into local days
+    pub timezone: ZoneInfo,
+    /// Requested time span, for the hourly view
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub window: Option<TimeWindow>,
+    pub entries: Vec<ForecastEntry>,
+    /// Per-local-day summaries, for views covering whole days
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<DailySummary>,
+}
+
+/// The time zone a forecast's local days refer to
+#[derive(Debug, Clone, Serialize)]
+pub struct ZoneInfo {
+    /// IANA name, or `UTC±HH:MM` for fixed offsets
+    pub name: String,
+    /// Offset from UTC at the start of the forecast, as `±HH:MM`
+    pub utc_offset: String,
+    /// Whether the zone was estimated from the longitude because none was
+    /// given
+    pub estimated: bool,
+}
+
+/// The views `get_weather` can produce
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForecastType {
+    Current,
+    Tomorrow,
+    Weekly,
+    /// Every step in a time window, from `get_hourly_forecast`
+    Hourly,
+}
+
+impl ForecastType {
+    /// The views `get_weather` offers
+    pub const ALL: [ForecastType; 3] = [Self::Current, Self::Tomorrow, Self::Weekly];
+
+    /// Name used in tool arguments and output
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Current => "current",
+            Self::Tomorrow => "tomorrow",
+            Self::Weekly => "weekly",
+            Self::Hourly => "hourly",
+        }
+    }
+}
+
+/// Time span an hourly forecast was asked for
+#[derive(Debug, Clone, Serialize)]
+pub struct TimeWindow {
+    /// RFC 3339, UTC
+    pub start: String,
+    /// RFC 3339, UTC
+    pub end: String,
+}
+
+/// Where the forecast applies
+#[derive(Debug, Clone, Serialize)]
+pub struct Location {
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Height above sea level in meters the forecast was computed for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub altitude: Option<f64>,
+}
+
+/// A moment such as a sunrise, with its local clock time
+#[derive(Debug, Clone, Serialize)]
+pub struct Event {
+    /// RFC 3339, UTC
+    pub time: String,
+    /// Wall-clock time in the location's zone, as HH:MM
+    pub local_time: String,
+}
+
+impl Event {
+    /// Rounds to the minute, the precision met.no gives rise and set times
+    pub fn new(timestamp: i64, tz: &TimeZone) -> Self {
+        let timestamp = (timestamp + 30).div_euclid(60) * 60;
+        let local = tz.local(timestamp);
+        Self {
+            time: timezone::format_utc(timestamp),
+            local_time: format!("{:02}:{:02}", local.hour(), local.seconds / 60 % 60),
+        }
+    }
+}
+
+/// Units of the numeric values in a [`Forecast`] or [`Nowcast`](crate::nowcast::Nowcast)
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Units {
+    pub temperature: TemperatureUnit,
+    /// Also used for wind gusts
+    pub wind_speed: SpeedUnit,
+    pub wind_direction: &'static str,
+    pub precipitation: PrecipitationUnit,
+    pub pressure: PressureUnit,
+    pub humidity: &'static str,
+    pub cloud_cover: &'static str,
+}
+
+impl Units {
+    /// The units of a preset, which single quantities can then override
+    pub fn preset(preset: UnitPreset) -> Self {
+        Self {
+            temperature: preset.temperature(),
+            wind_speed: preset.wind_speed(),
+            wind_direction: "degrees",
+            precipitation: preset.precipitation(),
+            pressure: preset.pressure(),
+            humidity: "percent",
+            cloud_cover: "percent",
+        }
+    }
+}
+
+/// met.no's own units, which forecasts are built in before conversion
+impl Default for Units {
+    fn default() -> Self {
+        Self::preset(UnitPreset::Metric)
+    }
+}
+
+/// Conditions at one point of the met.no timeseries
+#[derive(Debug, Clone, Serialize)]
+pub struct ForecastEntry {
+    /// Human-readable label such as "Now", or the local time of an hourly
+    /// step like "Sat 18 Oct 14:00"
+    pub label: String,
+    /// Start of the forecast step (RFC 3339, UTC)
+    pub time: String,
+    pub temperature: f64,
+    pub humidity: f64,
+    pub wind: Wind,
+    pub precipitation: Precipitation,
+    pub cloud_cover: f64,
+    pub pressure: f64,
+    pub symbol: Symbol,
+    pub comfort: Comfort,
+    /// Extra variables only available from met.no's `complete` product
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Details>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Wind {
+    pub speed: f64,
+    /// Direction the wind blows from, in degrees clockwise from north
+    pub from_direction: f64,
+    /// Compass name of `from_direction`
+    pub compass: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Precipitation {
+    pub amount: f64,
+    /// Length of the period `amount` accumulates over
+    pub period_hours: u32,
+}
+
+/// Conditions summarized over one local calendar day
+#[derive(Debug, Clone, Serialize)]
+pub struct DailySummary {
+    /// Human-readable label such as "Today" or "Sat 18 Oct"
+    pub label: String,
+    /// Local calendar date (YYYY-MM-DD)
+    pub date: String,
+    #[serde(flatten)]
+    pub stats: PeriodStats,
+    /// Night, morning, afternoon and evening, for single-day views
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub periods: Vec<PeriodSummary>,
+    /// Sunrise, sunset and moon, when requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sun_moon: Option<SunMoonEvents>,
+}
+
+/// Quarter of a local day
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DayPart {
+    /// 00–06
+    Night,
+    /// 06–12
+    Morning,
+    /// 12–18
+    Afternoon,
+    /// 18–24
+    Evening,
+}
+
+impl DayPart {
+    pub const ALL: [DayPart; 4] = [Self::Night, Self::Morning, Self::Afternoon, Self::Evening];
+
+    /// Local hour the part starts at
+    pub fn start_hour(self) -> u32 {
+        self as u32 * 6
+    }
+
+    /// Name in the given language
+    pub fn label(self, language: Language) -> &'static str {
+        language.messages().day_parts[self as usize]
+    }
+
+    fn icon(self) -> &'static str {
+        match self {
+            Self::Night => "🌙",
+            Self::Morning => "🌅",
+            Self::Afternoon => "☀️",
+            Self::Evening => "🌆",
+        }
+    }
+}
+
+/// Conditions summarized over part of a day
+#[derive(Debug, Clone, Serialize)]
+pub struct PeriodSummary {
+    /// Which quarter of the day this is
+    pub part: DayPart,
+    /// Localized name of `part`
+    pub label: String,
+    /// Start of the period (RFC 3339, UTC)
+    pub start: String,
+    /// End of the period (RFC 3339, UTC)
+    pub end: String,
+    #[serde(flatten)]
+    pub stats: PeriodStats,
+}
+
+/// Aggregates over the timeseries steps that start within a period
+#[derive(Debug, Clone, Serialize)]
+pub struct PeriodStats {
+    pub temperature_min: f64,
+    pub temperature_max: f64,
+    /// Total precipitation over the period
+    pub precipitation: f64,
+    /// Weather prevailing for the longest time, preferring daylight hours
+    pub symbol: Symbol,
+    pub wind_speed_max: f64,
+    /// Range of the apparent temperature
+    pub feels_like_min: f64,
+    pub feels_like_max: f64,
+    /// Strongest wind on the Beaufort scale
+    pub beaufort_max: u8,
+    /// Hours of forecast data the period covers
+    pub hours: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wind_gust_max: Option<f64>,
+    /// Highest chance of precipitation of any step, in percent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub precipitation_probability_max: Option<f64>,
+}
+
+impl PeriodStats {
+    fn convert_units(&mut self, units: &Units) {
+        self.temperature_min = units.temperature.convert(self.temperature_min);
+        self.temperature_max = units.temperature.convert(self.temperature_max);
+        self.precipitation = units.precipitation.convert(self.precipitation);
+        self.wind_speed_max = units.wind_speed.convert(self.wind_speed_max);
+        self.feels_like_min = units.temperature.convert(self.feels_like_min);
+        self.feels_like_max = units.temperature.convert(self.feels_like_max);
+        self.wind_gust_max = self.wind_gust_max.map(|v| units.wind_speed.convert(v));
+    }
+}
+
+/// Variables of the `complete` product; each is absent when met.no does not
+/// provide it for the step
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Details {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dew_point: Option<f64>,
+    /// Share of the area covered by fog, in percent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fog: Option<f64>,
+    pub cloud_layers: CloudLayers,
+    /// UV index assuming a clear sky
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uv_index: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wind_gust: Option<f64>,
+    /// Lowest temperature over the next 6 hours
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature_min: Option<f64>,
+    /// Highest temperature over the next 6 hours
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature_max: Option<f64>,
+    /// Chance of precipitation over the entry's precipitation period, in percent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub precipitation_probability: Option<f64>,
+    /// Chance of thunder over the next hour, in percent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thunder_probability: Option<f64>,
+}
+
+/// Cloud cover per altitude band, in percent
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CloudLayers {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub low: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub medium: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub high: Option<f64>,
+}
+
+impl CloudLayers {
+    fn is_empty(&self) -> bool {
+        self.low.is_none() && self.medium.is_none() && self.high.is_none()
+    }
+}
+
+impl Details {
+    /// Whether met.no provided none of the variables, as for `compact` data
+    pub fn is_empty(&self) -> bool {
+        self.dew_point.is_none()
+            && self.fog.is_none()
+            && self.cloud_layers.is_empty()
+            && self.uv_index.is_none()
+            && self.wind_gust.is_none()
+            && self.temperature_min.is_none()
+            && self.temperature_max.is_none()
+            && self.precipitation_probability.is_none()
+            && self.thunder_probability.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Symbol {
+    /// met.no symbol code, e.g. `partlycloudy_day`
+    pub code: String,
+    pub description: String,
+    /// Day, night or polar twilight form of the symbol, when it has one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variant: Option<Variant>,
+}
+
+impl Forecast {
+    /// JSON Schema of the serialized forecast, advertised as the tool's
+    /// `outputSchema`
+    pub fn output_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "location": location_schema(),
+                "forecast_type": {
+                    "type": "string",
+                    "enum": ["current", "tomorrow", "weekly", "hourly"]
+                },
+                "language": {
+                    "type": "string",
+                    "enum": ["en", "ko", "nb"],
+                    "description": "Language of labels and descriptions"
+                },
+                "updated_at": {
+                    "type": "string",
+                    "format": "date-time",
+                    "description": "When met.no last updated the forecast model"
+                },
+                "timezone": zone_info_schema(),
+                "window": {
+                    "type": "object",
+                    "description": "Requested time span of an hourly forecast (UTC)",
+                    "properties": {
+                        "start": { "type": "string", "format": "date-time" },
+                        "end": { "type": "string", "format": "date-time" }
+                    },
+                    "required": ["start", "end"]
+                },
+                "units": units_schema(),
+                "entries": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "label": { "type": "string" },
+                            "time": {
+                                "type": "string",
+                                "format": "date-time",
+                                "description": "Start of the forecast step (UTC)"
+                            },
+                            "temperature": { "type": "number" },
+                            "humidity": { "type": "number" },
+                            "wind": wind_schema(),
+                            "precipitation": {
+                                "type": "object",
+                                "properties": {
+                                    "amount": { "type": "number" },
+                                    "period_hours": {
+                                        "type": "integer",
+                                        "description": "Hours the amount accumulates over"
+                                    }
+                                },
+                                "required": ["amount", "period_hours"]
+                            },
+                            "cloud_cover": { "type": "number" },
+                            "pressure": { "type": "number" },
+                            "symbol": symbol_schema(),
+                            "comfort": comfort_schema(),
+                            "details": {
+                                "type": "object",
+                                "description": "Extra variables, present when detailed output was requested",
+                                "properties": {
+                                    "dew_point": { "type": "number" },
+                                    "fog": {
+                                        "type": "number",
+                                        "description": "Percent of the area covered by fog"
+                                    },
+                                    "cloud_layers": {
+                                        "type": "object",
+                                        "description": "Cloud cover per altitude band in percent",
+                                        "properties": {
+                                            "low": { "type": "number" },
+                                            "medium": { "type": "number" },
+                                            "high": { "type": "number" }
+                                        }
+                                    },
+                                    "uv_index": {
+                                        "type": "number",
+                                        "description": "UV index for a clear sky"
+                                    },
+                                    "wind_gust": { "type": "number" },
+                                    "temperature_min": {
+                                        "type": "number",
+                                        "description": "Lowest temperature over the next 6 hours"
+                                    },
+                                    "temperature_max": {
+                                        "type": "number",
+                                        "description": "Highest temperature over the next 6 hours"
+                                    },
+                                    "precipitation_probability": {
+                                        "type": "number",
+                                        "description": "Percent chance of precipitation over the precipitation period"
+                                    },
+                                    "thunder_probability": {
+                                        "type": "number",
+                                        "description": "Percent chance of thunder over the next hour"
+                                    }
+                                },
+                                "required": ["cloud_layers"]
+                            }
+                        },
+                        "required": [
+                            "label", "time", "temperature", "humidity", "wind",
+                            "precipitation", "cloud_cover", "pressure", "symbol", "comfort"
+                        ]
+                    }
+                },
+                "days": {
+                    "type": "array",
+                    "description": "Summaries per local calendar day",
+                    "items": daily_summary_schema()
+                }
+            },
+            "required": ["location", "forecast_type", "language", "units", "timezone", "entries"]
+        })
+    }
+
+    /// Converts every value from met.no's metric units into `units`
+    pub fn convert_units(mut self, units: Units) -> Self {
+        for entry in &mut self.entries {
+            entry.temperature = units.temperature.convert(entry.temperature);
+            entry.wind.speed = units.wind_speed.convert(entry.wind.speed);
+            entry.precipitation.amount = units.precipitation.convert(entry.precipitation.amount);
+            entry.pressure = units.pressure.convert(entry.pressure);
+            let comfort = &mut entry.comfort;
+            comfort.feels_like = units.temperature.convert(comfort.feels_like);
+            comfort.wind_chill = comfort.wind_chill.map(|t| units.temperature.convert(t));
+            comfort.heat_index = comfort.heat_index.map(|t| units.temperature.convert(t));
+            comfort.dew_point = units.temperature.convert(comfort.dew_point);
+            if let Some(details) = &mut entry.details {
+                details.dew_point = details.dew_point.map(|t| units.temperature.convert(t));
+                details.wind_gust = details.wind_gust.map(|v| units.wind_speed.convert(v));
+                details.temperature_min = details.temperature_min.map(|t| units.temperature.convert(t));
+                details.temperature_max = details.temperature_max.map(|t| units.temperature.convert(t));
+            }
+        }
+        for day in &mut self.days {
+            day.stats.convert_units(&units);
+            for period in &mut day.periods {
+                period.stats.convert_units(&units);
+            }
+        }
+        self.units = units;
+        self
+    }
+
+    /// Renders the forecast as the Markdown shown to the model
+    pub fn to_markdown(&self) -> String {
+        match self.forecast_type {
+            ForecastType::Current => self.current_markdown(),
+            ForecastType::Tomorrow => self.tomorrow_markdown(),
+            ForecastType::Weekly => self.weekly_markdown(),
+            ForecastType::Hourly => self.hourly_markdown(),
+        }
+    }
+
+    fn current_markdown(&self) -> String {
+        let m = self.language.messages();
+        let title = format!("🌍 **{}**", m.current_title);
+        let Some(entry) = self.entries.first() else {
+            return header(&title, &self.location, self.language);
+        };
+
+        let units = &self.units;
+        format!(
+            "{}\
+            🌡️ **{}:** {}\n\
+            ☁️ **{}:** {}\n\
+            💧 **{}:** {:.0}%\n\
+            🌬️ **{}:** {} {} ({} {})\n\
+            {}\
+            🌧️ **{} ({}h):** {}\n\
+            ☁️ **{}:** {:.0}%\n\
+            🔵 **{}:** {}\n\
+            {}\n\
+            {}",
+            header(&title, &self.location, self.language),
+            m.temperature,
+            units.temperature.format(entry.temperature),
+            m.condition,
+            entry.symbol.description,
+            m.humidity,
+            entry.humidity,
+            m.wind,
+            units.wind_speed.format(entry.wind.speed),
+            self.language.wind_from(entry.wind.compass),
+            m.wind_force,
+            entry.comfort.beaufort,
+            comfort_markdown(&entry.comfort, units, m),
+            m.precipitation,
+            entry.precipitation.period_hours,
+            units.precipitation.format(entry.precipitation.amount),
+            m.cloud_coverage,
+            entry.cloud_cover,
+            m.air_pressure,
+            units.pressure.format(entry.pressure),
+            details_markdown(entry, units, m),
+            m.attribution
+        )
+    }
+
+    fn tomorrow_markdown(&self) -> String {
+        let m = self.language.messages();
+        let mut forecast = header(&format!("📅 **{}**", m.tomorrow_title), &self.location, self.language);
+        let Some(day) = self.days.first() else {
+            return forecast;
+        };
+
+        forecast.push_str(&timezone_line(&self.timezone, self.language));
+        forecast.push_str(&format!("**{}** ({}): {}\n", day.label, day.date, stats_markdown(&day.stats, &self.units)));
+        if let Some(sun_moon) = &day.sun_moon {
+            forecast.push_str(&sun_moon.summary_markdown(self.language));
+            forecast.push('\n');
+        }
+        forecast.push('\n');
+
+        for period in &day.periods {
+            let start = period.part.start_hour();
+            forecast.push_str(&format!(
+                "{} **{}** ({:02}–{:02}): {}\n",
+                period.part.icon(),
+                period.label,
+                start,
+                start + 6,
+                stats_markdown(&period.stats, &self.units)
+            ));
+        }
+
+        forecast.push('\n');
+        forecast.push_str(&self.sun_moon_note());
+        forecast.push_str(m.attribution);
+        forecast
+    }
+
+    fn weekly_markdown(&self) -> String {
+        let m = self.language.messages();
+        let mut forecast = header(&format!("📆 **{}**", m.weekly_title), &self.location, self.language);
+        forecast.push_str(&timezone_line(&self.timezone, self.language));
+
+        for day in &self.days {
+            forecast.push_str(&format!("**{}**: {}\n", day.label, stats_markdown(&day.stats, &self.units)));
+            if let Some(sun_moon) = &day.sun_moon {
+                forecast.push_str(&format!("↳ {}\n", sun_moon.summary_markdown(self.language)));
+            }
+        }
+
+        forecast.push('\n');
+        forecast.push_str(&self.sun_moon_note());
+        forecast.push_str(m.attribution);
+        forecast
+    }
+
+    fn hourly_markdown(&self) -> String {
+        let m = self.language.messages();
+        let mut forecast = header(&format!("🕐 **{}**", m.hourly_title), &self.location, self.language);
+        forecast.push_str(&timezone_line(&self.timezone, self.language));
+
+        for entry in &self.entries {
+            let mut temperature = self.units.temperature.format(entry.temperature);
+            if entry.comfort.feels_like_source != FeelsLikeSource::AirTemperature {
+                temperature.push_str(&format!(
+                    " (🤗 {})",
+                    self.units.temperature.format(entry.comfort.feels_like)
+                ));
+            }
+            forecast.push_str(&format!(
+                "**{}**: {} | 🌡️ {} | 🌫️ {} | 💧 {} ({}h) | 🌬️ {} {} ({} {})\n",
+                entry.label,
+                entry.symbol.description,
+                temperature,
+                self.units.temperature.format(entry.comfort.dew_point),
+                self.units.precipitation.format(entry.precipitation.amount),
+                entry.precipitation.period_hours,
+                self.units.wind_speed.format(entry.wind.speed),
+                entry.wind.compass,
+                m.wind_force,
+                entry.comfort.beaufort
+            ));
+        }
+
+        forecast.push('\n');
+        forecast.push_str(m.attribution);
+        forecast
+    }
+
+    /// Notes sun and moon times of the days that were calculated offline
+    fn sun_moon_note(&self) -> String {
+        sun_moon::offline_note(self.days.iter().filter_map(|day| day.sun_moon.as_ref()), self.language)
+    }
+}
+
+/// Line naming the zone local days refer to, followed by a blank line
+pub fn timezone_line(zone: &ZoneInfo, language: Language) -> String {
+    let m = language.messages();
+    let mut line = format!("🕒 **{}:** {}", m.time_zone, zone.name);
+    if !zone.name.starts_with("UTC") {
+        line.push_str(&format!(" (UTC{})", zone.utc_offset));
+    }
+    if zone.estimated {
+        line.push_str(&format!(" – {}", m.estimated_from_longitude));
+    }
+    line.push_str("\n\n");
+    line
+}
+
+/// Title, location and coordinates lines shared by every view
+pub fn header(title: &str, location: &Location, language: Language) -> String {
+    let m = language.messages();
+    let altitude = match location.altitude {
+        Some(altitude) => format!("⛰️ **{}:** {:.0} m\n", m.altitude, altitude),
+        None => String::new(),
+    };
+    format!(
+        "{}\n\
+        📍 **{}:** {}\n\
+        🗺️ **{}:** {:.4}°, {:.4}°\n\
+        {}\n",
+        title, m.location, location.name, m.coordinates, location.latitude, location.longitude, altitude
+    )
+}
+
+/// JSON Schema of a [`Location`]
+pub fn location_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "name": { "type": "string" },
+            "latitude": { "type": "number" },
+            "longitude": { "type": "number" },
+            "altitude": {
+                "type": "number",
+                "description": "Height above sea level in meters the forecast was computed for"
+            }
+        },
+        "required": ["name", "latitude", "longitude"]
+    })
+}
+
+/// JSON Schema of an [`Event`]
+pub fn event_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "time": { "type": "string", "format": "date-time" },
+            "local_time": {
+                "type": "string",
+                "description": "Wall-clock time in the location's zone (HH:MM)"
+            }
+        },
+        "required": ["time", "local_time"]
+    })
+}
+
+/// JSON Schema of [`Units`]
+pub fn units_schema() -> Value {
+    json!({
+        "type": "object",
+        "description": "Units of the numeric values",
+        "properties": {
+            "temperature": { "type": "string", "enum": ["celsius", "fahrenheit"] },
+            "wind_speed": {
+                "type": "string",
+                "enum": ["m/s", "km/h", "mph", "knots", "beaufort"]
+            },
+            "wind_direction": { "type": "string" },
+            "precipitation": { "type": "string", "enum": ["mm", "in"] },
+            "pressure": { "type": "string", "enum": ["hPa", "inHg", "mmHg"] },
+            "humidity": { "type": "string" },
+            "cloud_cover": { "type": "string" }
+        }
+    })
+}
+
+/// JSON Schema of a [`Wind`]
+pub fn wind_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "speed": { "type": "number" },
+            "from_direction": {
+                "type": "number",
+                "description": "Degrees clockwise from north the wind blows from"
+            },
+            "compass": { "type": "string" }
+        },
+        "required": ["speed", "from_direction", "compass"]
+    })
+}
+
+/// JSON Schema of a [`Symbol`]
+pub fn symbol_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "code": {
+                "type": "string",
+                "description": "met.no weather symbol code"
+            },
+            "description": { "type": "string" },
+            "variant": {
+                "type": "string",
+                "enum": ["day", "night", "polartwilight"]
+            }
+        },
+        "required": ["code", "description"]
+    })
+}
+
+/// JSON Schema of a [`ZoneInfo`]
+pub fn zone_info_schema() -> Value {
+    json!({
+        "type": "object",
+        "description": "Time zone local days refer to",
+        "properties": {
+            "name": { "type": "string" },
+            "utc_offset": { "type": "string" },
+            "estimated": {
+                "type": "boolean",
+                "description": "True when the zone was estimated from the longitude"
+            }
+        },
+        "required": ["name", "utc_offset", "estimated"]
+    })
+}
+
+/// JSON Schema of a [`DailySummary`]
+fn daily_summary_schema() -> Value {
+    let mut schema = period_stats_schema();
+    schema["properties"]["label"] = json!({ "type": "string" });
+    schema["properties"]["date"] = json!({
+        "type": "string",
+        "format": "date",
+        "description": "Local calendar date"
+    });
+    schema["properties"]["periods"] = json!({
+        "type": "array",
+        "description": "Night (00-06), morning (06-12), afternoon (12-18) and evening (18-24) in local time",
+        "items": period_summary_schema()
+    });
+    schema["properties"]["sun_moon"] = sun_moon::events_schema();
+    schema["required"] = json!([
+        "label", "date", "temperature_min", "temperature_max", "precipitation",
+        "symbol", "wind_speed_max", "feels_like_min", "feels_like_max", "beaufort_max", "hours"
+    ]);
+    schema
+}
+
+/// JSON Schema of a [`PeriodSummary`]
+fn period_summary_schema() -> Value {
+    let mut schema = period_stats_schema();
+    schema["properties"]["part"] = json!({
+        "type": "string",
+        "enum": ["night", "morning", "afternoon", "evening"]
+    });
+    schema["properties"]["label"] = json!({ "type": "string" });
+    schema["properties"]["start"] = json!({ "type": "string", "format": "date-time" });
+    schema["properties"]["end"] = json!({ "type": "string", "format": "date-time" });
+    schema["required"] = json!([
+        "part", "label", "start", "end", "temperature_min", "temperature_max", "precipitation",
+        "symbol", "wind_speed_max", "feels_like_min", "feels_like_max", "beaufort_max", "hours"
+    ]);
+    schema
+}
+
+/// JSON Schema of the [`PeriodStats`] fields flattened into summaries
+fn period_stats_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "temperature_min": { "type": "number" },
+            "temperature_max": { "type": "number" },
+            "precipitation": {
+                "type": "number",
+                "description": "Total precipitation over the period"
+            },
+            "symbol": {
+                "type": "object",
+                "properties": {
+                    "code": { "type": "string" },
+                    "description": { "type": "string" },
+                    "variant": {
+                        "type": "string",
+                        "enum": ["day", "night", "polartwilight"]
+                    }
+                },
+                "required": ["code", "description"]
+            },
+            "wind_speed_max": { "type": "number" },
+            "feels_like_min": { "type": "number" },
+            "feels_like_max": { "type": "number" },
+            "beaufort_max": { "type": "integer", "minimum": 0, "maximum": 12 },
+            "hours": {
+                "type": "integer",
+                "description": "Hours of the period covered by forecast data"
+            },
+            "wind_gust_max": { "type": "number" },
+            "precipitation_probability_max": {
+                "type": "number",
+                "description": "Highest chance of precipitation in percent"
+            }
+        }
+    })
+}
+
+/// JSON Schema of a [`Comfort`]
+fn comfort_schema() -> Value {
+    json!({
+        "type": "object",
+        "description": "How the weather feels; temperatures use the temperature unit",
+        "properties": {
+            "feels_like": {
+                "type": "number",
+                "description": "Apparent temperature: wind chill at or below 10°C with wind, heat index from 27°C, else the air temperature"
+            },
+            "feels_like_source": {
+                "type": "string",
+                "enum": ["air_temperature", "wind_chill", "heat_index"]
+            },
+            "wind_chill": { "type": "number" },
+            "heat_index": { "type": "number" },
+            "humidex": {
+                "type": "number",
+                "description": "Canadian humidex, unitless; from 27°C"
+            },
+            "dew_point": {
+                "type": "number",
+                "description": "met.no's dew point when available, else computed from temperature and humidity"
+            },
+            "beaufort": { "type": "integer", "minimum": 0, "maximum": 12 }
+        },
+        "required": ["feels_like", "feels_like_source", "dew_point", "beaufort"]
+    })
+}
+
+/// Feels-like, humidex and dew point lines
+fn comfort_markdown(comfort: &Comfort, units: &Units, m: &Messages) -> String {
+    let mut lines = format!("🤗 **{}:** {}", m.feels_like, units.temperature.format(comfort.feels_like));
+    match comfort.feels_like_source {
+        FeelsLikeSource::WindChill => lines.push_str(&format!(" ({})", m.wind_chill)),
+        FeelsLikeSource::HeatIndex => lines.push_str(&format!(" ({})", m.heat_index)),
+        FeelsLikeSource::AirTemperature => {}
+    }
+    lines.push('\n');
+    if let Some(humidex) = comfort.humidex {
+        lines.push_str(&format!("🥵 **{}:** {:.0}\n", m.humidex, humidex));
+    }
+    lines.push_str(&format!("🌫️ **{}:** {}\n", m.dew_point, units.temperature.format(comfort.dew_point)));
+    lines
+}
+
+/// Lines for the variables of the `complete` product, empty for `compact`
+/// data
+fn details_markdown(entry: &ForecastEntry, units: &Units, m: &Messages) -> String {
+    let Some(details) = &entry.details else {
+        return String::new();
+    };
+
+    let mut lines = String::new();
+    if let Some(fog) = details.fog {
+        lines.push_str(&format!("🌁 **{}:** {:.0}%\n", m.fog, fog));
+    }
+    let layers = &details.cloud_layers;
+    if let (Some(low), Some(medium), Some(high)) = (layers.low, layers.medium, layers.high) {
+        let [low_name, medium_name, high_name] = m.cloud_layer_names;
+        lines.push_str(&format!(
+            "☁️ **{}:** {} {:.0}% · {} {:.0}% · {} {:.0}%\n",
+            m.cloud_layers, low_name, low, medium_name, medium, high_name, high
+        ));
+    }
+    if let Some(uv_index) = details.uv_index {
+        lines.push_str(&format!("🔆 **{}:** {:.1}\n", m.uv_index, uv_index));
+    }
+    if let Some(gust) = details.wind_gust {
+        lines.push_str(&format!("💨 **{}:** {}\n", m.wind_gusts, units.wind_speed.format(gust)));
+    }
+    if let (Some(min), Some(max)) = (details.temperature_min, details.temperature_max) {
+        lines.push_str(&format!(
+            "↕️ **{} (6h):** {} / {}\n",
+            m.min_max,
+            units.temperature.format(min),
+            units.temperature.format(max)
+        ));
+    }
+    if let Some(probability) = details.precipitation_probability {
+        lines.push_str(&format!(
+            "☔ **{} ({}h):** {:.0}%\n",
+            m.precipitation_chance, entry.precipitation.period_hours, probability
+        ));
+    }
+    if let Some(probability) = details.thunder_probability {
+        lines.push_str(&format!("⚡ **{} (1h):** {:.0}%\n", m.thunder_chance, probability));
+    }
+    lines
+}
+
+/// One-line summary of aggregated conditions
+fn stats_markdown(stats: &PeriodStats, units: &Units) -> String {
+    let mut line = format!(
+        "{} | 🌡️ {}–{} | 💧 {} | 🌬️ {}",
+        stats.symbol.description,
+        units.temperature.number(stats.temperature_min),
+        units.temperature.format(stats.temperature_max),
+        units.precipitation.format(stats.precipitation),
+        units.wind_speed.format(stats.wind_speed_max)
+    );
+    // Only worth a mention when wind or heat take it beyond the air temperature
+    if stats.feels_like_min < stats.temperature_min || stats.feels_like_max > stats.temperature_max {
+        line.push_str(&format!(
+            " | 🤗 {}–{}",
+            units.temperature.number(stats.feels_like_min),
+            units.temperature.format(stats.feels_like_max)
+        ));
+    }
+    if let Some(probability) = stats.precipitation_probability_max {
+        line.push_str(&format!(" | ☔ {:.0}%", probability));
+    }
+    if let Some(gust) = stats.wind_gust_max {
+        line.push_str(&format!(" | 💨 {}", units.wind_speed.format(gust)));
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(period_hours: u32) -> ForecastEntry {
+        ForecastEntry {
+            label: "Sat 4 May 14:00".to_string(),
+            time: "2024-05-04T12:00:00Z".to_string(),
+            temperature: 12.0,
+            humidity: 60.0,
+            wind: Wind { speed: 6.0, from_direction: 225.0, compass: "SW" },
+            precipitation: Precipitation { amount: 1.4, period_hours },
+            cloud_cover: 75.0,
+            pressure: 1012.0,
+            symbol: Symbol { code: "rain".to_string(), description: "Rain".to_string(), variant: None },
+            comfort: Comfort::new(12.0, 60.0, 6.0, None),
+            details: None,
+        }
+    }
+
+    fn forecast(forecast_type: ForecastType, entries: Vec<ForecastEntry>) -> Forecast {
+        Forecast {
+            location: Location { name: "Oslo".to_string(), latitude: 59.91, longitude: 10.75, altitude: None },
+            forecast_type,
+            language: Language::En,
+            updated_at: None,
+            units: Units::default(),
+            timezone: ZoneInfo { name: "Europe/Oslo".to_string(), utc_offset: "+02:00".to_string(), estimated: false },
+            window: None,
+            entries,
+            days: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn current_view_labels_precipitation_with_its_period() {
+        let one_hour = forecast(ForecastType::Current, vec![entry(1)]).to_markdown();
+        assert!(one_hour.contains("**Precipitation (1h):** 1.4 mm"), "{}", one_hour);
+
+        // Past met.no's hourly range only six-hour sums are left
+        let six_hours = forecast(ForecastType::Current, vec![entry(6)]).to_markdown();
+        assert!(six_hours.contains("**Precipitation (6h):** 1.4 mm"), "{}", six_hours);
+    }
+
+    #[test]
+    fn hourly_view_shows_dew_point_and_wind_force() {
+        let text = forecast(ForecastType::Hourly, vec![entry(1)]).to_markdown();
+        let line = text.lines().find(|line| line.starts_with("**Sat 4 May 14:00**")).unwrap();
+        assert_eq!(line, "**Sat 4 May 14:00**: Rain | 🌡️ 12.0°C | 🌫️ 4.5°C | 💧 1.4 mm (1h) | 🌬️ 6.0 m/s SW (force 4)");
+    }
+}
@@ -0,0 +1,537 @@
+use anyhow::Result;
+use axum::{
+    body::Bytes,
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::{self, Next},
+    response::{
+        sse::{Event, Sse},
+        IntoResponse, Response,
+    },
+    routing::post,
+    Json, Router,
+};
+use reqwest::Url;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, AcquireError, Semaphore, SemaphorePermit};
+use tokio::time::Instant;
+use tokio_stream::{wrappers::UnboundedReceiverStream, StreamExt};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::weather::WeatherClient;
+use crate::{calls_tool, handle_guarded, parse_error_response, Notifier, Session};
+
+/// Path of the single MCP endpoint
+const MCP_PATH: &str = "/mcp";
+/// Header carrying the session id assigned on `initialize`
+const SESSION_HEADER: &str = "mcp-session-id";
+/// Header carrying the protocol revision negotiated during `initialize`
+const PROTOCOL_VERSION_HEADER: &str = "mcp-protocol-version";
+/// Sessions that see no request for this long are dropped
+const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+/// Most sessions kept at once; beyond it the least recently used is dropped
+const MAX_SESSIONS: usize = 1000;
+
+/// State shared by all HTTP handlers
+struct AppState {
+    weather_client: WeatherClient,
+    /// Client sessions created by a successful `initialize`, by session id
+    sessions: Mutex<HashMap<String, HttpSession>>,
+    /// Bounds the number of tool calls processed at once
+    limiter: Semaphore,
+    /// Browser origins allowed besides the local machine's
+    allowed_origins: Vec<String>,
+}
+
+impl AppState {
+    /// Waits for a slot if `message` calls a tool; other messages need none
+    async fn permit(&self, message: &Value) -> Result<Option<SemaphorePermit<'_>>, AcquireError> {
+        if calls_tool(message) {
+            self.limiter.acquire().await.map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// A client session and when its client was last heard from
+struct HttpSession {
+    session: Arc<Session>,
+    last_used: Instant,
+}
+
+/// Serves MCP over Streamable HTTP until Ctrl-C is received
+pub async fn serve(
+    addr: SocketAddr,
+    weather_client: WeatherClient,
+    max_in_flight: usize,
+    allowed_origins: Vec<String>,
+) -> Result<()> {
+    let app = router(weather_client, max_in_flight, allowed_origins);
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    info!("Streamable HTTP transport listening on http://{}{}", addr, MCP_PATH);
+
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+
+    info!("Server shutting down gracefully");
+    Ok(())
+}
+
+/// Routes for the MCP endpoint
+fn router(weather_client: WeatherClient, max_in_flight: usize, allowed_origins: Vec<String>) -> Router {
+    let state = Arc::new(AppState {
+        weather_client,
+        sessions: Mutex::new(HashMap::new()),
+        limiter: Semaphore::new(max_in_flight),
+        allowed_origins,
+    });
+
+    Router::new()
+        .route(MCP_PATH, post(handle_post).get(handle_get).delete(handle_delete))
+        .layer(middleware::from_fn_with_state(state.clone(), check_origin))
+        .with_state(state)
+}
+
+/// Refuses requests a browser sends on behalf of a foreign web page, which
+/// could otherwise reach a local server through DNS rebinding
+async fn check_origin(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    if let Some(origin) = request.headers().get(header::ORIGIN) {
+        let origin = origin.to_str().unwrap_or_default();
+        if !is_allowed_origin(origin, &state.allowed_origins) {
+            warn!("Rejected request from origin {:?}", origin);
+            return (StatusCode::FORBIDDEN, "Origin not allowed").into_response();
+        }
+    }
+    next.run(request).await
+}
+
+/// Handles a JSON-RPC message POSTed by the client
+async fn handle_post(State(state): State<Arc<AppState>>, headers: HeaderMap, body: Bytes) -> Response {
+    debug!("Received: {}", String::from_utf8_lossy(&body));
+
+    let message: Value = match serde_json::from_slice(&body) {
+        Ok(message) => message,
+        Err(e) => {
+            warn!("Failed to parse request: {}", e);
+            return (StatusCode::BAD_REQUEST, Json(parse_error_response(&e))).into_response();
+        }
+    };
+
+    let is_initialize = message.get("method").and_then(|m| m.as_str()) == Some("initialize");
+    let session = if is_initialize {
+        Arc::new(Session::new())
+    } else {
+        let mut sessions = state.sessions.lock().unwrap();
+        match session_id(&headers) {
+            Some(id) => match find_session(&mut sessions, id, Instant::now()) {
+                Some(session) => session,
+                None => {
+                    debug!("Unknown session: {}", id);
+                    return StatusCode::NOT_FOUND.into_response();
+                }
+            },
+            None => {
+                return (StatusCode::BAD_REQUEST, "Missing Mcp-Session-Id header").into_response();
+            }
+        }
+    };
+
+    // After initialization the client must echo the negotiated revision
+    if let Some(version) = headers.get(PROTOCOL_VERSION_HEADER) {
+        let negotiated = session.protocol_version();
+        if negotiated.is_some_and(|negotiated| version.to_str().ok() != Some(negotiated)) {
+            warn!("Rejected MCP-Protocol-Version {:?}", version);
+            return (StatusCode::BAD_REQUEST, "Unsupported MCP-Protocol-Version").into_response();
+        }
+    }
+
+    let has_requests = match &message {
+        Value::Array(batch) => batch.iter().any(is_request),
+        message => is_request(message),
+    };
+    let wants_progress = message.pointer("/params/_meta/progressToken").is_some();
+
+    // Progress notifications need an SSE response to travel on
+    if !is_initialize && is_request(&message) && wants_progress && accepts_sse(&headers) {
+        return stream_response(state, message, session);
+    }
+
+    let response = {
+        let Ok(_permit) = state.permit(&message).await else {
+            return StatusCode::SERVICE_UNAVAILABLE.into_response();
+        };
+        handle_guarded(message, &state.weather_client, &session, &Notifier::disabled()).await
+    };
+
+    // Notifications and responses are only acknowledged
+    if !has_requests {
+        return StatusCode::ACCEPTED.into_response();
+    }
+
+    // The client cancelled every request while they were running
+    let Some(response) = response else {
+        return StatusCode::NO_CONTENT.into_response();
+    };
+
+    let response_is_error = response.get("error").is_some();
+    let mut http_response = Json(response).into_response();
+
+    if is_initialize && !response_is_error {
+        let id = Uuid::new_v4().to_string();
+        store_session(&mut state.sessions.lock().unwrap(), id.clone(), session, Instant::now());
+        info!("Created session {}", id);
+
+        if let Ok(value) = HeaderValue::from_str(&id) {
+            http_response.headers_mut().insert(SESSION_HEADER, value);
+        }
+    }
+
+    http_response
+}
+
+/// Answers a request with an SSE stream carrying its notifications followed
+/// by the response itself
+fn stream_response(state: Arc<AppState>, message: Value, session: Arc<Session>) -> Response {
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        let Ok(_permit) = state.permit(&message).await else {
+            return;
+        };
+
+        let notifier = Notifier::new(tx.clone());
+        if let Some(response) = handle_guarded(message, &state.weather_client, &session, &notifier).await {
+            let _ = tx.send(response.to_string());
+        }
+    });
+
+    // The stream ends once the task above drops its senders
+    let stream = UnboundedReceiverStream::new(rx).map(|data| {
+        debug!("Sending: {}", data);
+        Ok::<_, Infallible>(Event::default().event("message").data(data))
+    });
+
+    Sse::new(stream).into_response()
+}
+
+/// Refuses to open a standalone SSE stream: the server never sends
+/// messages outside a response, so there would be nothing to deliver.
+/// Progress travels on the SSE response of the POST it belongs to.
+async fn handle_get() -> Response {
+    StatusCode::METHOD_NOT_ALLOWED.into_response()
+}
+
+/// Terminates a session at the client's request
+async fn handle_delete(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    let Some(id) = session_id(&headers) else {
+        return (StatusCode::BAD_REQUEST, "Missing Mcp-Session-Id header").into_response();
+    };
+
+    if state.sessions.lock().unwrap().remove(id).is_some() {
+        info!("Terminated session {}", id);
+        StatusCode::NO_CONTENT.into_response()
+    } else {
+        StatusCode::NOT_FOUND.into_response()
+    }
+}
+
+/// Looks up a session and marks it used; one idle for too long is dropped
+/// as if it had been deleted
+fn find_session(sessions: &mut HashMap<String, HttpSession>, id: &str, now: Instant) -> Option<Arc<Session>> {
+    let entry = sessions.get_mut(id)?;
+    if now.duration_since(entry.last_used) > SESSION_IDLE_TIMEOUT {
+        sessions.remove(id);
+        info!("Session {} expired", id);
+        return None;
+    }
+    entry.last_used = now;
+    Some(entry.session.clone())
+}
+
+/// Adds a session after dropping idle ones, and the least recently used
+/// one if the server is still at capacity
+fn store_session(sessions: &mut HashMap<String, HttpSession>, id: String, session: Arc<Session>, now: Instant) {
+    sessions.retain(|_, entry| now.duration_since(entry.last_used) <= SESSION_IDLE_TIMEOUT);
+    if sessions.len() >= MAX_SESSIONS {
+        let oldest = sessions
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(id, _)| id.clone());
+        if let Some(oldest) = oldest {
+            sessions.remove(&oldest);
+            info!("Dropped least recently used session {}", oldest);
+        }
+    }
+    sessions.insert(id, HttpSession { session, last_used: now });
+}
+
+/// Whether a decoded message is a request (carries an id) rather than a
+/// notification or a response
+fn is_request(message: &Value) -> bool {
+    message.get("method").is_some() && message.get("id").is_some_and(|id| !id.is_null())
+}
+
+/// Whether the client's `Accept` header allows an SSE response
+fn accepts_sse(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("text/event-stream"))
+}
+
+/// Whether a browser origin may use the server: pages served from the local
+/// machine always can, others only when listed in `allowed`
+fn is_allowed_origin(origin: &str, allowed: &[String]) -> bool {
+    if allowed
+        .iter()
+        .any(|allowed| allowed.trim_end_matches('/').eq_ignore_ascii_case(origin))
+    {
+        return true;
+    }
+    Url::parse(origin).is_ok_and(|url| {
+        matches!(url.scheme(), "http" | "https")
+            && matches!(url.host_str(), Some("localhost" | "127.0.0.1" | "[::1]"))
+    })
+}
+
+/// Extracts the `Mcp-Session-Id` header value, if any
+fn session_id(headers: &HeaderMap) -> Option<&str> {
+    headers.get(SESSION_HEADER).and_then(|v| v.to_str().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{self, Body};
+    use axum::http::Method;
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        // Nothing listens on the discard port, so tool calls fail fast
+        let weather_client = WeatherClient::builder()
+            .base_url("http://127.0.0.1:9")
+            .contact("tests@example.com")
+            .max_retries(0)
+            .build()
+            .unwrap();
+        router(weather_client, 4, vec!["https://agents.example.com".to_string()])
+    }
+
+    async fn send(app: &Router, method: Method, session: Option<&str>, body: Option<Value>) -> Response {
+        send_from(app, None, method, session, body).await
+    }
+
+    async fn send_from(
+        app: &Router,
+        origin: Option<&str>,
+        method: Method,
+        session: Option<&str>,
+        body: Option<Value>,
+    ) -> Response {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(MCP_PATH)
+            .header(header::ACCEPT, "application/json, text/event-stream")
+            .header(header::CONTENT_TYPE, "application/json");
+        if let Some(origin) = origin {
+            request = request.header(header::ORIGIN, origin);
+        }
+        if let Some(session) = session {
+            request = request.header(SESSION_HEADER, session);
+        }
+        let body = body.map_or_else(Body::empty, |body| Body::from(body.to_string()));
+        app.clone().oneshot(request.body(body).unwrap()).await.unwrap()
+    }
+
+    async fn text(response: Response) -> String {
+        let bytes = body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    /// Runs the handshake and returns the new session's id
+    async fn initialize(app: &Router) -> String {
+        let response = send(
+            app,
+            Method::POST,
+            None,
+            Some(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "initialize",
+                "params": {
+                    "protocolVersion": "2025-06-18",
+                    "capabilities": {},
+                    "clientInfo": {"name": "tests", "version": "1.0"}
+                }
+            })),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let id = session_id(response.headers()).unwrap().to_string();
+        let body: Value = serde_json::from_str(&text(response).await).unwrap();
+        assert_eq!(body["result"]["protocolVersion"], "2025-06-18");
+
+        let initialized = json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
+        let response = send(app, Method::POST, Some(&id), Some(initialized)).await;
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        id
+    }
+
+    fn ping() -> Option<Value> {
+        Some(json!({"jsonrpc": "2.0", "id": 2, "method": "ping"}))
+    }
+
+    #[tokio::test]
+    async fn initialize_opens_a_session() {
+        let app = app();
+        let id = initialize(&app).await;
+
+        let response = send(&app, Method::POST, Some(&id), ping()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = serde_json::from_str(&text(response).await).unwrap();
+        assert_eq!(body, json!({"jsonrpc": "2.0", "id": 2, "result": {}}));
+    }
+
+    #[tokio::test]
+    async fn requests_need_a_known_session() {
+        let app = app();
+        assert_eq!(send(&app, Method::POST, None, ping()).await.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            send(&app, Method::POST, Some("no-such-session"), ping()).await.status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_malformed_json() {
+        let app = app();
+        let request = Request::post(MCP_PATH).body(Body::from("{not json")).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: Value = serde_json::from_str(&text(response).await).unwrap();
+        assert_eq!(body["error"]["code"], -32700);
+    }
+
+    #[tokio::test]
+    async fn checks_the_origin_of_browser_requests() {
+        let app = app();
+        let id = initialize(&app).await;
+
+        for origin in ["http://localhost:3000", "http://127.0.0.1", "http://[::1]:8080", "https://agents.example.com"] {
+            let response = send_from(&app, Some(origin), Method::POST, Some(&id), ping()).await;
+            assert_eq!(response.status(), StatusCode::OK, "{}", origin);
+        }
+        for origin in ["https://evil.example", "http://localhost.evil.example", "null"] {
+            let response = send_from(&app, Some(origin), Method::POST, Some(&id), ping()).await;
+            assert_eq!(response.status(), StatusCode::FORBIDDEN, "{}", origin);
+        }
+        let response = send_from(&app, Some("https://evil.example"), Method::DELETE, Some(&id), None).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn idle_sessions_expire() {
+        let app = app();
+        let id = initialize(&app).await;
+
+        tokio::time::advance(SESSION_IDLE_TIMEOUT / 2).await;
+        assert_eq!(send(&app, Method::POST, Some(&id), ping()).await.status(), StatusCode::OK);
+        // Each request restarts the idle clock
+        tokio::time::advance(SESSION_IDLE_TIMEOUT / 2 + Duration::from_secs(60)).await;
+        assert_eq!(send(&app, Method::POST, Some(&id), ping()).await.status(), StatusCode::OK);
+        tokio::time::advance(SESSION_IDLE_TIMEOUT + Duration::from_secs(1)).await;
+        assert_eq!(send(&app, Method::POST, Some(&id), ping()).await.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn drops_the_least_recently_used_session_at_capacity() {
+        let start = Instant::now();
+        let mut sessions = HashMap::new();
+        for n in 0..MAX_SESSIONS {
+            let at = start + Duration::from_secs(n as u64);
+            store_session(&mut sessions, n.to_string(), Arc::new(Session::new()), at);
+        }
+        let now = start + Duration::from_secs(MAX_SESSIONS as u64);
+        assert!(find_session(&mut sessions, "0", now).is_some());
+
+        store_session(&mut sessions, "new".to_string(), Arc::new(Session::new()), now);
+        assert_eq!(sessions.len(), MAX_SESSIONS);
+        assert!(sessions.contains_key("0"));
+        assert!(!sessions.contains_key("1"));
+        assert!(sessions.contains_key("new"));
+    }
+
+    #[test]
+    fn storing_a_session_sweeps_idle_ones() {
+        let start = Instant::now();
+        let mut sessions = HashMap::new();
+        store_session(&mut sessions, "old".to_string(), Arc::new(Session::new()), start);
+        let later = start + SESSION_IDLE_TIMEOUT + Duration::from_secs(1);
+        store_session(&mut sessions, "new".to_string(), Arc::new(Session::new()), later);
+        assert_eq!(sessions.keys().collect::<Vec<_>>(), ["new"]);
+    }
+
+    #[tokio::test]
+    async fn get_is_not_allowed() {
+        let app = app();
+        let id = initialize(&app).await;
+        assert_eq!(
+            send(&app, Method::GET, Some(&id), None).await.status(),
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+    }
+
+    #[tokio::test]
+    async fn delete_ends_the_session() {
+        let app = app();
+        let id = initialize(&app).await;
+
+        assert_eq!(send(&app, Method::DELETE, None, None).await.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(send(&app, Method::DELETE, Some(&id), None).await.status(), StatusCode::NO_CONTENT);
+        assert_eq!(send(&app, Method::POST, Some(&id), ping()).await.status(), StatusCode::NOT_FOUND);
+        assert_eq!(send(&app, Method::DELETE, Some(&id), None).await.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn streams_progress_before_the_response() {
+        let app = app();
+        let id = initialize(&app).await;
+
+        let call = json!({
+            "jsonrpc": "2.0",
+            "id": 3,
+            "method": "tools/call",
+            "params": {
+                "name": "get_weather",
+                "arguments": {"latitude": 59.91, "longitude": 10.75},
+                "_meta": {"progressToken": "weather"}
+            }
+        });
+        let response = send(&app, Method::POST, Some(&id), Some(call)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/event-stream");
+
+        let events: Vec<Value> = text(response)
+            .await
+            .lines()
+            .filter_map(|line| line.strip_prefix("data: "))
+            .map(|data| serde_json::from_str(data).unwrap())
+            .collect();
+        let (response, notifications) = events.split_last().unwrap();
+        assert_eq!(notifications[0]["method"], "notifications/progress");
+        assert_eq!(notifications[0]["params"]["progressToken"], "weather");
+        assert_eq!(response["id"], 3);
+        assert_eq!(response["result"]["isError"], true);
+    }
+}
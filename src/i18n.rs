@@ -0,0 +1,543 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::symbol::WeatherSymbol;
+use crate::timezone::Date;
+
+/// Language of the text in tool results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    /// English
+    #[default]
+    En,
+    /// Korean
+    Ko,
+    /// Norwegian Bokmål
+    Nb,
+}
+
+/// Fixed strings of the Markdown output in one language
+pub struct Messages {
+    pub current_title: &'static str,
+    pub tomorrow_title: &'static str,
+    pub weekly_title: &'static str,
+    pub hourly_title: &'static str,
+    pub sun_moon_title: &'static str,
+    pub nowcast_title: &'static str,
+    pub location: &'static str,
+    pub coordinates: &'static str,
+    pub altitude: &'static str,
+    pub time_zone: &'static str,
+    pub estimated_from_longitude: &'static str,
+    pub temperature: &'static str,
+    pub condition: &'static str,
+    pub humidity: &'static str,
+    pub wind: &'static str,
+    pub precipitation: &'static str,
+    pub cloud_coverage: &'static str,
+    pub air_pressure: &'static str,
+    pub dew_point: &'static str,
+    pub feels_like: &'static str,
+    pub wind_chill: &'static str,
+    pub heat_index: &'static str,
+    pub humidex: &'static str,
+    /// Precedes the Beaufort number, as in "force 4"
+    pub wind_force: &'static str,
+    pub fog: &'static str,
+    pub cloud_layers: &'static str,
+    /// Low, medium and high cloud
+    pub cloud_layer_names: [&'static str; 3],
+    pub uv_index: &'static str,
+    pub wind_gusts: &'static str,
+    pub min_max: &'static str,
+    pub precipitation_chance: &'static str,
+    pub thunder_chance: &'static str,
+    pub sunrise: &'static str,
+    pub sunset: &'static str,
+    pub solar_noon: &'static str,
+    pub daylight: &'static str,
+    pub midnight_sun: &'static str,
+    pub polar_night: &'static str,
+    pub moonrise: &'static str,
+    pub moonset: &'static str,
+    /// New moon, waxing crescent, first quarter, ..., waning crescent
+    pub moon_phases: [&'static str; 8],
+    /// Notes sun and moon times computed locally instead of by met.no
+    pub calculated_offline: &'static str,
+    pub precipitation_rate: &'static str,
+    pub raining_now: &'static str,
+    pub radar_coverage: &'static str,
+    /// Radar coverage ok, temporarily unavailable and missing
+    pub radar_states: [&'static str; 3],
+    pub now: &'static str,
+    pub today: &'static str,
+    pub tomorrow: &'static str,
+    pub unknown: &'static str,
+    pub unknown_location: &'static str,
+    /// Night, morning, afternoon and evening
+    pub day_parts: [&'static str; 4],
+    /// Sunday first
+    pub weekdays: [&'static str; 7],
+    pub months: [&'static str; 12],
+    /// North, northeast, ..., northwest
+    pub compass: [&'static str; 8],
+    /// Credit line required by the met.no terms of service
+    pub attribution: &'static str,
+}
+
+const EN: Messages = Messages {
+    current_title: "Current Weather",
+    tomorrow_title: "Tomorrow's Weather",
+    weekly_title: "7-Day Weather Forecast",
+    hourly_title: "Hourly Weather Forecast",
+    sun_moon_title: "Sun & Moon",
+    nowcast_title: "Precipitation Nowcast",
+    location: "Location",
+    coordinates: "Coordinates",
+    altitude: "Altitude",
+    time_zone: "Time zone",
+    estimated_from_longitude: "estimated from longitude",
+    temperature: "Temperature",
+    condition: "Condition",
+    humidity: "Humidity",
+    wind: "Wind",
+    precipitation: "Precipitation",
+    cloud_coverage: "Cloud coverage",
+    air_pressure: "Air pressure",
+    dew_point: "Dew point",
+    feels_like: "Feels like",
+    wind_chill: "wind chill",
+    heat_index: "heat index",
+    humidex: "Humidex",
+    wind_force: "force",
+    fog: "Fog",
+    cloud_layers: "Cloud layers",
+    cloud_layer_names: ["low", "medium", "high"],
+    uv_index: "UV index (clear sky)",
+    wind_gusts: "Wind gusts",
+    min_max: "Min/Max",
+    precipitation_chance: "Chance of precipitation",
+    thunder_chance: "Chance of thunder",
+    sunrise: "Sunrise",
+    sunset: "Sunset",
+    solar_noon: "Solar noon",
+    daylight: "Daylight",
+    midnight_sun: "Midnight sun – the sun does not set",
+    polar_night: "Polar night – the sun does not rise",
+    moonrise: "Moonrise",
+    moonset: "Moonset",
+    moon_phases: ["New moon", "Waxing crescent", "First quarter", "Waxing gibbous", "Full moon", "Waning gibbous", "Last quarter", "Waning crescent"],
+    calculated_offline: "Calculated offline because met.no's Sunrise API could not be reached",
+    precipitation_rate: "Precipitation rate",
+    raining_now: "Precipitation now",
+    radar_coverage: "Radar coverage",
+    radar_states: ["OK", "temporarily unavailable; precipitation may be less accurate", "none; precipitation may be less accurate"],
+    now: "Now",
+    today: "Today",
+    tomorrow: "Tomorrow",
+    unknown: "Unknown",
+    unknown_location: "Unknown Location",
+    day_parts: ["Night", "Morning", "Afternoon", "Evening"],
+    weekdays: ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"],
+    months: ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"],
+    compass: ["North", "Northeast", "East", "Southeast", "South", "Southwest", "West", "Northwest"],
+    attribution: "*Data from YR.no (Norwegian Meteorological Institute)*",
+};
+
+const KO: Messages = Messages {
+    current_title: "현재 날씨",
+    tomorrow_title: "내일 날씨",
+    weekly_title: "7일 예보",
+    hourly_title: "시간별 예보",
+    sun_moon_title: "해와 달",
+    nowcast_title: "초단기 강수 예보",
+    location: "위치",
+    coordinates: "좌표",
+    altitude: "고도",
+    time_zone: "시간대",
+    estimated_from_longitude: "경도로 추정",
+    temperature: "기온",
+    condition: "날씨",
+    humidity: "습도",
+    wind: "바람",
+    precipitation: "강수량",
+    cloud_coverage: "구름량",
+    air_pressure: "기압",
+    dew_point: "이슬점",
+    feels_like: "체감 온도",
+    wind_chill: "바람 냉각",
+    heat_index: "열지수",
+    humidex: "휴미덱스",
+    wind_force: "풍력 계급",
+    fog: "안개",
+    cloud_layers: "구름층",
+    cloud_layer_names: ["하층", "중층", "상층"],
+    uv_index: "자외선 지수 (맑은 하늘 기준)",
+    wind_gusts: "돌풍",
+    min_max: "최저/최고",
+    precipitation_chance: "강수 확률",
+    thunder_chance: "뇌우 확률",
+    sunrise: "일출",
+    sunset: "일몰",
+    solar_noon: "남중",
+    daylight: "낮 길이",
+    midnight_sun: "백야 – 해가 지지 않음",
+    polar_night: "극야 – 해가 뜨지 않음",
+    moonrise: "월출",
+    moonset: "월몰",
+    moon_phases: ["삭", "초승달", "상현달", "차가는 달", "보름달", "기우는 달", "하현달", "그믐달"],
+    calculated_offline: "met.no 일출 API에 연결할 수 없어 오프라인으로 계산함",
+    precipitation_rate: "강수 강도",
+    raining_now: "현재 강수 중",
+    radar_coverage: "레이더 관측",
+    radar_states: ["정상", "일시적으로 사용 불가, 강수 정보가 부정확할 수 있음", "관측 범위 밖, 강수 정보가 부정확할 수 있음"],
+    now: "지금",
+    today: "오늘",
+    tomorrow: "내일",
+    unknown: "알 수 없음",
+    unknown_location: "알 수 없는 위치",
+    day_parts: ["새벽", "오전", "오후", "저녁"],
+    weekdays: ["일", "월", "화", "수", "목", "금", "토"],
+    months: ["1월", "2월", "3월", "4월", "5월", "6월", "7월", "8월", "9월", "10월", "11월", "12월"],
+    compass: ["북", "북동", "동", "남동", "남", "남서", "서", "북서"],
+    attribution: "*데이터 출처: YR.no (노르웨이 기상연구소)*",
+};
+
+const NB: Messages = Messages {
+    current_title: "Været nå",
+    tomorrow_title: "Været i morgen",
+    weekly_title: "Værvarsel for 7 dager",
+    hourly_title: "Værvarsel time for time",
+    sun_moon_title: "Sol og måne",
+    nowcast_title: "Nedbør neste to timer",
+    location: "Sted",
+    coordinates: "Koordinater",
+    altitude: "Høyde",
+    time_zone: "Tidssone",
+    estimated_from_longitude: "anslått ut fra lengdegrad",
+    temperature: "Temperatur",
+    condition: "Vær",
+    humidity: "Luftfuktighet",
+    wind: "Vind",
+    precipitation: "Nedbør",
+    cloud_coverage: "Skydekke",
+    air_pressure: "Lufttrykk",
+    dew_point: "Duggpunkt",
+    feels_like: "Føles som",
+    wind_chill: "vindavkjøling",
+    heat_index: "varmeindeks",
+    humidex: "Humidex",
+    wind_force: "styrke",
+    fog: "Tåke",
+    cloud_layers: "Skylag",
+    cloud_layer_names: ["lavt", "middels", "høyt"],
+    uv_index: "UV-indeks (skyfritt)",
+    wind_gusts: "Vindkast",
+    min_max: "Min/maks",
+    precipitation_chance: "Sjanse for nedbør",
+    thunder_chance: "Sjanse for torden",
+    sunrise: "Soloppgang",
+    sunset: "Solnedgang",
+    solar_noon: "Sol i sør",
+    daylight: "Dagslengde",
+    midnight_sun: "Midnattssol – sola går ikke ned",
+    polar_night: "Mørketid – sola går ikke opp",
+    moonrise: "Måneoppgang",
+    moonset: "Månenedgang",
+    moon_phases: ["Nymåne", "Voksende månesigd", "Første kvarter", "Voksende måne", "Fullmåne", "Minkende måne", "Siste kvarter", "Minkende månesigd"],
+    calculated_offline: "Beregnet lokalt fordi soloppgangs-API-et til met.no ikke svarte",
+    precipitation_rate: "Nedbørintensitet",
+    raining_now: "Nedbør nå",
+    radar_coverage: "Radardekning",
+    radar_states: ["OK", "midlertidig utilgjengelig; nedbøren kan være mindre nøyaktig", "ingen; nedbøren kan være mindre nøyaktig"],
+    now: "Nå",
+    today: "I dag",
+    tomorrow: "I morgen",
+    unknown: "Ukjent",
+    unknown_location: "Ukjent sted",
+    day_parts: ["Natt", "Morgen", "Ettermiddag", "Kveld"],
+    weekdays: ["søn.", "man.", "tir.", "ons.", "tor.", "fre.", "lør."],
+    months: ["jan.", "feb.", "mar.", "apr.", "mai", "jun.", "jul.", "aug.", "sep.", "okt.", "nov.", "des."],
+    compass: ["nord", "nordøst", "øst", "sørøst", "sør", "sørvest", "vest", "nordvest"],
+    attribution: "*Data fra Yr (Meteorologisk institutt)*",
+};
+
+impl Language {
+    pub const ALL: [Language; 3] = [Self::En, Self::Ko, Self::Nb];
+
+    /// ISO 639-1 code used in tool arguments and output
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::Ko => "ko",
+            Self::Nb => "nb",
+        }
+    }
+
+    pub fn messages(self) -> &'static Messages {
+        match self {
+            Self::En => &EN,
+            Self::Ko => &KO,
+            Self::Nb => &NB,
+        }
+    }
+
+    /// Weekday and date, such as "Sat 18 Oct", "10월 18일 (토)" or
+    /// "lør. 18. okt."
+    pub fn date_label(self, date: Date) -> String {
+        let messages = self.messages();
+        let weekday = messages.weekdays[date.weekday().to_sunday_zero_offset() as usize];
+        let month = messages.months[date.month() as usize - 1];
+        match self {
+            Self::En => format!("{} {} {}", weekday, date.day(), month),
+            Self::Ko => format!("{} {}일 ({})", month, date.day(), weekday),
+            Self::Nb => format!("{} {}. {}", weekday, date.day(), month),
+        }
+    }
+
+    /// A span of minutes, such as "16 h 22 min" or "16시간 22분"
+    pub fn duration(self, minutes: u32) -> String {
+        let (hours, minutes) = (minutes / 60, minutes % 60);
+        match self {
+            Self::En => format!("{} h {} min", hours, minutes),
+            Self::Ko => format!("{}시간 {}분", hours, minutes),
+            Self::Nb => format!("{} t {} min", hours, minutes),
+        }
+    }
+
+    /// When precipitation starts, given as local HH:MM and minutes from now
+    pub fn precipitation_starts(self, time: &str, minutes: i64) -> String {
+        match self {
+            Self::En => format!("Precipitation starts at {} (in {} min)", time, minutes),
+            Self::Ko => format!("{}에 강수 시작 ({}분 후)", time, minutes),
+            Self::Nb => format!("Nedbør fra kl. {} (om {} min)", time, minutes),
+        }
+    }
+
+    /// When precipitation stops, following [`Self::precipitation_starts`]
+    /// or [`Messages::raining_now`]
+    pub fn precipitation_stops(self, time: &str, minutes: i64) -> String {
+        match self {
+            Self::En => format!("stops at {} (in {} min)", time, minutes),
+            Self::Ko => format!("{}에 그침 ({}분 후)", time, minutes),
+            Self::Nb => format!("opphold fra kl. {} (om {} min)", time, minutes),
+        }
+    }
+
+    /// Precipitation lasting beyond the end of a nowcast at `time`
+    pub fn precipitation_continues(self, time: &str) -> String {
+        match self {
+            Self::En => format!("continues past {}", time),
+            Self::Ko => format!("{} 이후에도 계속", time),
+            Self::Nb => format!("fortsetter etter kl. {}", time),
+        }
+    }
+
+    /// No precipitation until the end of a nowcast at `time`
+    pub fn dry_until(self, time: &str) -> String {
+        match self {
+            Self::En => format!("No precipitation expected before {}", time),
+            Self::Ko => format!("{}까지 강수 없음", time),
+            Self::Nb => format!("Ingen nedbør ventet før kl. {}", time),
+        }
+    }
+
+    /// Where the wind blows from, such as "from North" or "북풍"
+    pub fn wind_from(self, compass: &str) -> String {
+        match self {
+            Self::En => format!("from {}", compass),
+            Self::Ko => format!("{}풍", compass),
+            Self::Nb => format!("fra {}", compass),
+        }
+    }
+
+    /// Localized name of a weather symbol
+    pub fn symbol_name(self, symbol: WeatherSymbol) -> &'static str {
+        match self {
+            Self::En => symbol.name(),
+            Self::Ko => korean_symbol_name(symbol),
+            Self::Nb => norwegian_symbol_name(symbol),
+        }
+    }
+}
+
+fn korean_symbol_name(symbol: WeatherSymbol) -> &'static str {
+    use WeatherSymbol::*;
+    match symbol {
+        ClearSky => "맑음",
+        Fair => "대체로 맑음",
+        PartlyCloudy => "구름 조금",
+        Cloudy => "흐림",
+        Fog => "안개",
+        LightRainShowers => "약한 소나기",
+        RainShowers => "소나기",
+        HeavyRainShowers => "강한 소나기",
+        LightRainShowersAndThunder => "약한 소나기와 천둥",
+        RainShowersAndThunder => "소나기와 천둥",
+        HeavyRainShowersAndThunder => "강한 소나기와 천둥",
+        LightSleetShowers => "약한 소낙성 진눈깨비",
+        SleetShowers => "소낙성 진눈깨비",
+        HeavySleetShowers => "강한 소낙성 진눈깨비",
+        LightSleetShowersAndThunder => "약한 소낙성 진눈깨비와 천둥",
+        SleetShowersAndThunder => "소낙성 진눈깨비와 천둥",
+        HeavySleetShowersAndThunder => "강한 소낙성 진눈깨비와 천둥",
+        LightSnowShowers => "약한 소낙눈",
+        SnowShowers => "소낙눈",
+        HeavySnowShowers => "강한 소낙눈",
+        LightSnowShowersAndThunder => "약한 소낙눈과 천둥",
+        SnowShowersAndThunder => "소낙눈과 천둥",
+        HeavySnowShowersAndThunder => "강한 소낙눈과 천둥",
+        LightRain => "약한 비",
+        Rain => "비",
+        HeavyRain => "강한 비",
+        LightRainAndThunder => "약한 비와 천둥",
+        RainAndThunder => "비와 천둥",
+        HeavyRainAndThunder => "강한 비와 천둥",
+        LightSleet => "약한 진눈깨비",
+        Sleet => "진눈깨비",
+        HeavySleet => "강한 진눈깨비",
+        LightSleetAndThunder => "약한 진눈깨비와 천둥",
+        SleetAndThunder => "진눈깨비와 천둥",
+        HeavySleetAndThunder => "강한 진눈깨비와 천둥",
+        LightSnow => "약한 눈",
+        Snow => "눈",
+        HeavySnow => "많은 눈",
+        LightSnowAndThunder => "약한 눈과 천둥",
+        SnowAndThunder => "눈과 천둥",
+        HeavySnowAndThunder => "많은 눈과 천둥",
+    }
+}
+
+fn norwegian_symbol_name(symbol: WeatherSymbol) -> &'static str {
+    use WeatherSymbol::*;
+    match symbol {
+        ClearSky => "Klarvær",
+        Fair => "Lettskyet",
+        PartlyCloudy => "Delvis skyet",
+        Cloudy => "Skyet",
+        Fog => "Tåke",
+        LightRainShowers => "Lette regnbyger",
+        RainShowers => "Regnbyger",
+        HeavyRainShowers => "Kraftige regnbyger",
+        LightRainShowersAndThunder => "Lette regnbyger og torden",
+        RainShowersAndThunder => "Regnbyger og torden",
+        HeavyRainShowersAndThunder => "Kraftige regnbyger og torden",
+        LightSleetShowers => "Lette sluddbyger",
+        SleetShowers => "Sluddbyger",
+        HeavySleetShowers => "Kraftige sluddbyger",
+        LightSleetShowersAndThunder => "Lette sluddbyger og torden",
+        SleetShowersAndThunder => "Sluddbyger og torden",
+        HeavySleetShowersAndThunder => "Kraftige sluddbyger og torden",
+        LightSnowShowers => "Lette snøbyger",
+        SnowShowers => "Snøbyger",
+        HeavySnowShowers => "Kraftige snøbyger",
+        LightSnowShowersAndThunder => "Lette snøbyger og torden",
+        SnowShowersAndThunder => "Snøbyger og torden",
+        HeavySnowShowersAndThunder => "Kraftige snøbyger og torden",
+        LightRain => "Lett regn",
+        Rain => "Regn",
+        HeavyRain => "Kraftig regn",
+        LightRainAndThunder => "Lett regn og torden",
+        RainAndThunder => "Regn og torden",
+        HeavyRainAndThunder => "Kraftig regn og torden",
+        LightSleet => "Lett sludd",
+        Sleet => "Sludd",
+        HeavySleet => "Kraftig sludd",
+        LightSleetAndThunder => "Lett sludd og torden",
+        SleetAndThunder => "Sludd og torden",
+        HeavySleetAndThunder => "Kraftig sludd og torden",
+        LightSnow => "Lett snø",
+        Snow => "Snø",
+        HeavySnow => "Kraftig snø",
+        LightSnowAndThunder => "Lett snø og torden",
+        SnowAndThunder => "Snø og torden",
+        HeavySnowAndThunder => "Kraftig snø og torden",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// Every string of a catalog
+    fn texts(m: &Messages) -> Vec<&'static str> {
+        let mut texts = vec![
+            m.current_title, m.tomorrow_title, m.weekly_title, m.hourly_title, m.sun_moon_title, m.nowcast_title,
+            m.location, m.coordinates, m.altitude, m.time_zone, m.estimated_from_longitude, m.temperature,
+            m.condition, m.humidity, m.wind, m.precipitation, m.cloud_coverage, m.air_pressure, m.dew_point,
+            m.feels_like, m.wind_chill, m.heat_index, m.humidex, m.wind_force, m.fog, m.cloud_layers, m.uv_index,
+            m.wind_gusts, m.min_max, m.precipitation_chance, m.thunder_chance, m.sunrise, m.sunset, m.solar_noon,
+            m.daylight, m.midnight_sun, m.polar_night, m.moonrise, m.moonset, m.calculated_offline,
+            m.precipitation_rate, m.raining_now, m.radar_coverage, m.now, m.today, m.tomorrow, m.unknown,
+            m.unknown_location, m.attribution,
+        ];
+        texts.extend(m.cloud_layer_names);
+        texts.extend(m.moon_phases);
+        texts.extend(m.radar_states);
+        texts.extend(m.day_parts);
+        texts.extend(m.weekdays);
+        texts.extend(m.months);
+        texts.extend(m.compass);
+        texts
+    }
+
+    /// Strings that read the same in English and Norwegian
+    const SHARED_WITH_ENGLISH: [&str; 2] = ["Humidex", "OK"];
+
+    #[test]
+    fn every_catalog_is_translated() {
+        let english = texts(&EN);
+        for language in [Language::Ko, Language::Nb] {
+            let translated = texts(language.messages());
+            assert_eq!(translated.len(), english.len());
+            for (text, original) in translated.iter().zip(&english) {
+                assert!(!text.trim().is_empty(), "{}: {:?} is empty", language.as_str(), original);
+                assert!(
+                    text != original || SHARED_WITH_ENGLISH.contains(text),
+                    "{}: {:?} is not translated",
+                    language.as_str(),
+                    original
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn names_in_a_list_are_distinct() {
+        for language in Language::ALL {
+            let m = language.messages();
+            let lists: [&[&str]; 7] = [
+                &m.cloud_layer_names, &m.moon_phases, &m.radar_states, &m.day_parts, &m.weekdays, &m.months, &m.compass,
+            ];
+            for list in lists {
+                assert_eq!(list.iter().collect::<HashSet<_>>().len(), list.len(), "{}: {:?}", language.as_str(), list);
+            }
+
+            let symbols: HashSet<_> = WeatherSymbol::ALL.iter().map(|&s| language.symbol_name(s)).collect();
+            assert_eq!(symbols.len(), WeatherSymbol::ALL.len(), "{}", language.as_str());
+            assert!(symbols.iter().all(|name| !name.is_empty()));
+        }
+    }
+
+    #[test]
+    fn symbol_names_are_translated() {
+        for symbol in WeatherSymbol::ALL {
+            assert_ne!(Language::Ko.symbol_name(symbol), symbol.name());
+            assert_ne!(Language::Nb.symbol_name(symbol), symbol.name());
+        }
+    }
+
+    #[test]
+    fn formats_dates_and_durations_per_language() {
+        let date = jiff::civil::date(2025, 10, 18);
+        assert_eq!(Language::En.date_label(date), "Sat 18 Oct");
+        assert_eq!(Language::Ko.date_label(date), "10월 18일 (토)");
+        assert_eq!(Language::Nb.date_label(date), "lør. 18. okt.");
+
+        assert_eq!(Language::En.duration(982), "16 h 22 min");
+        assert_eq!(Language::Ko.duration(982), "16시간 22분");
+        assert_eq!(Language::Nb.duration(982), "16 t 22 min");
+    }
+}
@@ -1,12 +1,124 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use clap::{Parser, ValueEnum};
+use futures::future::join_all;
+use futures::FutureExt;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::io::{self, BufRead, Write};
+use std::net::SocketAddr;
+use std::panic::AssertUnwindSafe;
+use std::path::PathBuf;
+use std::time::Duration;
 use tracing::{debug, error, info};
 
+mod astronomy;
+mod cache;
+mod comfort;
+mod config;
+mod forecast;
+mod http;
+mod i18n;
+mod nowcast;
+mod rate_limit;
+mod session;
+mod stdio;
+mod sun_moon;
+mod symbol;
+mod timezone;
+mod tools;
+mod units;
 mod weather;
+use cache::{DiskCache, ResponseCache};
+use config::Config;
+use i18n::Language;
+use session::{Lifecycle, Notifier, Outcome, Session};
 use weather::WeatherClient;
 
+/// MCP protocol revisions this server implements, newest first
+const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+/// First protocol revision with `outputSchema` and `structuredContent`
+const STRUCTURED_OUTPUT_SINCE: &str = "2025-06-18";
+
+/// Command-line options for the server
+#[derive(Debug, Parser)]
+#[command(name = "yr-weather-mcp", version, about = "MCP server for YR.no weather forecasts")]
+struct Cli {
+    /// Transport used to talk to MCP clients
+    #[arg(long, value_enum, default_value_t = Transport::Stdio)]
+    transport: Transport,
+    /// Address to listen on when using the HTTP transport
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    bind: SocketAddr,
+    /// Browser origin, such as https://agents.example.com, allowed to call the
+    /// HTTP transport besides pages served from localhost; repeatable
+    #[arg(long = "allowed-origin", value_name = "ORIGIN")]
+    allowed_origins: Vec<String>,
+    /// Maximum number of tool calls processed concurrently
+    #[arg(
+        long,
+        default_value_t = 16,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    max_in_flight: usize,
+    /// Directory for a persistent forecast cache that survives restarts
+    #[arg(long)]
+    cache_dir: Option<PathBuf>,
+    /// Size limit of the persistent cache in megabytes
+    #[arg(long, default_value_t = 50)]
+    cache_max_mb: u64,
+    /// Seconds allowed for connecting to met.no
+    #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u64).range(1..))]
+    connect_timeout: u64,
+    /// Seconds allowed for a whole met.no request, including the response body
+    #[arg(long, default_value_t = 15, value_parser = clap::value_parser!(u64).range(1..))]
+    request_timeout: u64,
+    /// Retries after a connection failure or a 502/503/504 from met.no
+    #[arg(long, default_value_t = 2)]
+    max_retries: u32,
+    /// JSON file providing defaults for the settings below
+    #[arg(long, env = "YR_WEATHER_CONFIG")]
+    config: Option<PathBuf>,
+    /// Contact e-mail or URL sent to met.no in the User-Agent
+    #[arg(long, env = "YR_WEATHER_CONTACT")]
+    contact: Option<String>,
+    /// Complete User-Agent to send instead of the generated one
+    #[arg(long, env = "YR_WEATHER_USER_AGENT")]
+    user_agent: Option<String>,
+    /// Base URL of the met.no API [default: https://api.met.no/weatherapi]
+    #[arg(long, env = "YR_WEATHER_BASE_URL")]
+    base_url: Option<String>,
+    /// Proxy URL for requests to met.no
+    #[arg(long, env = "YR_WEATHER_PROXY")]
+    proxy: Option<String>,
+    /// PEM file with additional root certificates to trust
+    #[arg(long, env = "YR_WEATHER_CA_BUNDLE")]
+    ca_bundle: Option<PathBuf>,
+    /// Language of forecasts when a tool call does not choose one [default: en]
+    #[arg(long, value_enum, env = "YR_WEATHER_LANGUAGE")]
+    language: Option<Language>,
+}
+
+impl Cli {
+    /// Takes the settings not given as flags or environment variables from
+    /// the config file
+    fn with_config(mut self, config: Config) -> Self {
+        self.contact = self.contact.or(config.contact);
+        self.user_agent = self.user_agent.or(config.user_agent);
+        self.base_url = self.base_url.or(config.base_url);
+        self.proxy = self.proxy.or(config.proxy);
+        self.ca_bundle = self.ca_bundle.or(config.ca_bundle);
+        self.language = self.language.or(config.language);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Transport {
+    /// Line-delimited JSON-RPC over stdin/stdout
+    Stdio,
+    /// MCP Streamable HTTP (POST + optional SSE) on a single endpoint
+    Http,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct JsonRpcRequest {
     jsonrpc: String,
@@ -37,12 +149,13 @@ struct JsonRpcError {
 struct InitializeParams {
     #[serde(rename = "protocolVersion")]
     protocol_version: String,
+    #[serde(default)]
     capabilities: Value,
     #[serde(rename = "clientInfo")]
     client_info: ClientInfo,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct ClientInfo {
     name: String,
     version: String,
@@ -52,11 +165,15 @@ struct ClientInfo {
 struct ToolCall {
     name: String,
     arguments: Option<Value>,
+    #[serde(rename = "_meta")]
+    meta: Option<Value>,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     use tracing_subscriber::EnvFilter;
+
+    let cli = Cli::parse();
     
     // Improved logging initialization with error handling
     let env_filter = EnvFilter::try_from_default_env()
@@ -69,79 +186,354 @@ async fn main() -> Result<()> {
 
     info!("YR Weather MCP Server starting...");
     
-    // Handle potential error from WeatherClient::new
-    let weather_client = WeatherClient::new()?;
-    let stdin = io::stdin();
-    let mut stdout = io::stdout();
-    let mut stdin_lock = stdin.lock();
-    
-    loop {
-        let mut line = String::new();
-        match stdin_lock.read_line(&mut line) {
-            Ok(0) => {
-                // EOF reached
-                info!("EOF reached, shutting down server");
-                break;
-            }
-            Ok(_) => {
-                let line = line.trim();
-                if line.is_empty() {
-                    continue;
-                }
-                
-                debug!("Received: {}", line);
-                
-                let request: JsonRpcRequest = match serde_json::from_str(line) {
-                    Ok(req) => req,
-                    Err(e) => {
-                        error!("Failed to parse request: {}", e);
-                        continue;
-                    }
-                };
-                
-                let response = handle_request(request, &weather_client).await;
-                
-                // Don't send response for notifications
-                if response.id.is_some() || response.error.is_some() {
-                    let response_str = serde_json::to_string(&response)?;
-                    debug!("Sending: {}", response_str);
-                    writeln!(stdout, "{}", response_str)?;
-                    stdout.flush()?;
-                } else {
-                    debug!("Notification received, no response sent");
-                }
-            }
-            Err(e) => {
-                error!("Failed to read from stdin: {}", e);
-                break;
+    let config = match &cli.config {
+        Some(path) => Config::load(path)?,
+        None => Config::default(),
+    };
+    let cli = cli.with_config(config);
+
+    let mut builder = WeatherClient::builder()
+        .connect_timeout(Duration::from_secs(cli.connect_timeout))
+        .request_timeout(Duration::from_secs(cli.request_timeout))
+        .max_retries(cli.max_retries);
+    if let Some(contact) = cli.contact {
+        builder = builder.contact(contact);
+    }
+    if let Some(user_agent) = cli.user_agent {
+        builder = builder.user_agent(user_agent);
+    }
+    if let Some(base_url) = cli.base_url {
+        info!("Using met.no API at {}", base_url);
+        builder = builder.base_url(base_url);
+    }
+    if let Some(proxy) = cli.proxy {
+        builder = builder.proxy(proxy);
+    }
+    if let Some(ca_bundle) = cli.ca_bundle {
+        builder = builder.ca_bundle(ca_bundle);
+    }
+    if let Some(language) = cli.language {
+        builder = builder.language(language);
+    }
+    if let Some(cache_dir) = cli.cache_dir {
+        let disk_cache = DiskCache::open(cache_dir.clone(), cli.cache_max_mb.saturating_mul(1024 * 1024))
+            .with_context(|| format!("Failed to open cache directory {}", cache_dir.display()))?;
+        info!("Persistent forecast cache in {}", cache_dir.display());
+        builder = builder.cache(ResponseCache::with_disk(disk_cache));
+    }
+    let weather_client = builder.build()?;
+
+    match cli.transport {
+        Transport::Stdio => stdio::serve(weather_client, cli.max_in_flight).await,
+        Transport::Http => http::serve(cli.bind, weather_client, cli.max_in_flight, cli.allowed_origins).await,
+    }
+}
+
+/// Handles one decoded JSON-RPC message, which may be a batch.
+///
+/// Returns the JSON to send back, or `None` when the message consisted only
+/// of notifications or cancelled requests.
+async fn handle_message(
+    message: Value,
+    weather_client: &WeatherClient,
+    session: &Session,
+    notifier: &Notifier,
+) -> Option<Value> {
+    match message {
+        Value::Array(batch) if batch.is_empty() => {
+            let response = error_response(None, -32600, "Invalid Request: empty batch".to_string());
+            to_json(&response)
+        }
+        Value::Array(batch) => {
+            let responses: Vec<Value> = join_all(
+                batch
+                    .into_iter()
+                    .map(|message| handle_single_message(message, weather_client, session, notifier)),
+            )
+            .await
+            .into_iter()
+            .flatten()
+            .collect();
+
+            // A batch of notifications gets no reply at all
+            if responses.is_empty() {
+                None
+            } else {
+                Some(Value::Array(responses))
             }
         }
+        message => handle_single_message(message, weather_client, session, notifier).await,
+    }
+}
+
+/// Handles a message like [`handle_message`], but answers every request
+/// in it with -32603 if a handler panics, so the client is not left waiting
+/// for a response that would never come
+async fn handle_guarded(
+    message: Value,
+    weather_client: &WeatherClient,
+    session: &Session,
+    notifier: &Notifier,
+) -> Option<Value> {
+    let ids = request_ids(&message);
+    let is_batch = message.is_array();
+    let handled = AssertUnwindSafe(handle_message(message, weather_client, session, notifier))
+        .catch_unwind()
+        .await;
+
+    match handled {
+        Ok(response) => response,
+        Err(_) => {
+            error!("Handler panicked while processing request(s) {:?}", ids);
+            internal_errors(ids, is_batch)
+        }
+    }
+}
+
+/// Whether a message, or any message of a batch, calls a tool. Only tool
+/// calls do slow work against met.no, so only they count against
+/// `--max-in-flight`.
+fn calls_tool(message: &Value) -> bool {
+    let is_call = |message: &Value| message.get("method").and_then(|m| m.as_str()) == Some("tools/call");
+    match message {
+        Value::Array(batch) => batch.iter().any(is_call),
+        message => is_call(message),
     }
-    
-    info!("Server shutting down gracefully");
-    Ok(())
 }
 
-/// Handles incoming JSON-RPC requests
-async fn handle_request(request: JsonRpcRequest, weather_client: &WeatherClient) -> JsonRpcResponse {
+/// Ids of the requests in a message or batch; notifications have none
+fn request_ids(message: &Value) -> Vec<Value> {
+    let id = |message: &Value| {
+        message
+            .get("id")
+            .filter(|id| message.get("method").is_some() && (id.is_string() || id.is_number()))
+            .cloned()
+    };
+    match message {
+        Value::Array(batch) => batch.iter().filter_map(id).collect(),
+        message => id(message).into_iter().collect(),
+    }
+}
+
+/// -32603 responses for the requests with `ids`, shaped like the message
+/// they answer
+fn internal_errors(ids: Vec<Value>, is_batch: bool) -> Option<Value> {
+    let mut responses: Vec<Value> = ids
+        .into_iter()
+        .filter_map(|id| to_json(&error_response(Some(id), -32603, "Internal error".to_string())))
+        .collect();
+    match (is_batch, responses.len()) {
+        (_, 0) => None,
+        (false, _) => responses.pop(),
+        (true, _) => Some(Value::Array(responses)),
+    }
+}
+
+/// Validates and handles a single (non-batch) JSON-RPC message
+async fn handle_single_message(
+    message: Value,
+    weather_client: &WeatherClient,
+    session: &Session,
+    notifier: &Notifier,
+) -> Option<Value> {
+    // Echo the id back when it is usable so the client can match the error
+    let id = message
+        .get("id")
+        .filter(|id| id.is_string() || id.is_number())
+        .cloned();
+
+    let response = match parse_request(message) {
+        Ok(request) => handle_request(request, weather_client, session, notifier).await?,
+        Err(reason) => error_response(id, -32600, format!("Invalid Request: {}", reason)),
+    };
+    to_json(&response)
+}
+
+/// Decodes a JSON-RPC request object, explaining how it deviates from the
+/// JSON-RPC 2.0 structure if it does
+fn parse_request(message: Value) -> Result<JsonRpcRequest, String> {
+    if !message.is_object() {
+        return Err("expected a request object".to_string());
+    }
+
+    let request: JsonRpcRequest = serde_json::from_value(message).map_err(|e| e.to_string())?;
+
+    if request.jsonrpc != "2.0" {
+        return Err(format!("unsupported jsonrpc version {:?}", request.jsonrpc));
+    }
+    if request.id.as_ref().is_some_and(|id| !id.is_string() && !id.is_number()) {
+        return Err("id must be a string or a number".to_string());
+    }
+    if request.params.as_ref().is_some_and(|p| !p.is_object() && !p.is_array()) {
+        return Err("params must be an object or an array".to_string());
+    }
+
+    Ok(request)
+}
+
+/// Builds the -32700 response for input that isn't valid JSON
+fn parse_error_response(e: &serde_json::Error) -> JsonRpcResponse {
+    error_response(None, -32700, format!("Parse error: {}", e))
+}
+
+/// Builds a JSON-RPC error response
+fn error_response(id: Option<Value>, code: i32, message: String) -> JsonRpcResponse {
+    JsonRpcResponse {
+        jsonrpc: "2.0".to_string(),
+        id,
+        result: None,
+        error: Some(JsonRpcError {
+            code,
+            message,
+            data: None,
+        }),
+    }
+}
+
+/// Serializes a response, logging instead of failing the whole message
+fn to_json(response: &JsonRpcResponse) -> Option<Value> {
+    serde_json::to_value(response)
+        .map_err(|e| error!("Failed to serialize response: {}", e))
+        .ok()
+}
+
+/// Handles a validated JSON-RPC request or notification.
+///
+/// Returns `None` when nothing must be sent back: for notifications and for
+/// requests the client cancelled while they were running.
+async fn handle_request(
+    request: JsonRpcRequest,
+    weather_client: &WeatherClient,
+    session: &Session,
+    notifier: &Notifier,
+) -> Option<JsonRpcResponse> {
     // Handle notifications (no response needed)
     if request.method.starts_with("notifications/") {
-        // For notifications, return empty response that won't be sent
-        return JsonRpcResponse {
-            jsonrpc: "2.0".to_string(),
-            id: None,
-            result: Some(json!(null)),
-            error: None,
-        };
+        handle_notification(&request, session);
+        return None;
     }
-    
+
+    // A request without an id is a notification too and must not be answered
+    let Some(id) = request.id.clone() else {
+        debug!("Ignoring {} sent without an id", request.method);
+        return None;
+    };
+
+    // `initialize` must not be cancelled, so it is never registered
+    if request.method == "initialize" {
+        return Some(dispatch_request(request, weather_client, session, notifier).await);
+    }
+
+    match session
+        .run_cancellable(&id, dispatch_request(request, weather_client, session, notifier))
+        .await
+    {
+        Outcome::Completed(response) => Some(response),
+        Outcome::Cancelled => {
+            info!("Request {} cancelled, response suppressed", id);
+            None
+        }
+        Outcome::DuplicateId => Some(error_response(
+            Some(id.clone()),
+            -32600,
+            format!("Invalid Request: request {} is already in progress", id),
+        )),
+    }
+}
+
+/// Handles client notifications
+fn handle_notification(request: &JsonRpcRequest, session: &Session) {
+    match request.method.as_str() {
+        "notifications/initialized" => session.mark_initialized(),
+        "notifications/cancelled" => {
+            let params = request.params.as_ref();
+            if let Some(id) = params.and_then(|p| p.get("requestId")) {
+                let reason = params
+                    .and_then(|p| p.get("reason"))
+                    .and_then(|r| r.as_str())
+                    .unwrap_or("no reason given");
+                debug!("Client cancelled request {}: {}", id, reason);
+                session.cancel(id);
+            }
+        }
+        method => debug!("Ignoring notification: {}", method),
+    }
+}
+
+/// Picks the protocol revision to use for a client requesting `requested`:
+/// the same one if we support it, otherwise our latest
+fn negotiate_protocol_version(requested: &str) -> &'static str {
+    SUPPORTED_PROTOCOL_VERSIONS
+        .iter()
+        .find(|version| **version == requested)
+        .copied()
+        .unwrap_or(SUPPORTED_PROTOCOL_VERSIONS[0])
+}
+
+/// Whether the session's protocol revision knows about `outputSchema` and
+/// `structuredContent`, going by its place in [`SUPPORTED_PROTOCOL_VERSIONS`]
+fn supports_structured_output(session: &Session) -> bool {
+    let age = |version: &str| SUPPORTED_PROTOCOL_VERSIONS.iter().position(|v| *v == version);
+    match (session.protocol_version().and_then(age), age(STRUCTURED_OUTPUT_SINCE)) {
+        (Some(version), Some(since)) => version <= since,
+        _ => false,
+    }
+}
+
+/// Rejects requests that aren't allowed at the session's point in the
+/// initialization handshake
+fn check_lifecycle(request: &JsonRpcRequest, session: &Session) -> Option<JsonRpcResponse> {
+    let message = match (request.method.as_str(), session.lifecycle()) {
+        ("initialize" | "ping", _) => return None,
+        ("tools/call", Lifecycle::Ready) => return None,
+        ("tools/call", Lifecycle::Initializing) => {
+            "Server not initialized: send notifications/initialized before calling tools"
+        }
+        (_, Lifecycle::Uninitialized) => "Server not initialized: send initialize first",
+        _ => return None,
+    };
+    Some(error_response(request.id.clone(), -32002, message.to_string()))
+}
+
+/// Routes a JSON-RPC request to its method handler
+async fn dispatch_request(
+    request: JsonRpcRequest,
+    weather_client: &WeatherClient,
+    session: &Session,
+    notifier: &Notifier,
+) -> JsonRpcResponse {
+    if let Some(response) = check_lifecycle(&request, session) {
+        return response;
+    }
+
     match request.method.as_str() {
         "initialize" => {
+            let params: InitializeParams = match request.params.map(serde_json::from_value) {
+                Some(Ok(params)) => params,
+                Some(Err(e)) => {
+                    return error_response(request.id, -32602, format!("Invalid initialize parameters: {}", e));
+                }
+                None => {
+                    return error_response(request.id, -32602, "Missing initialize parameters".to_string());
+                }
+            };
+
+            let protocol_version = negotiate_protocol_version(&params.protocol_version);
+            debug!("Client capabilities: {}", params.capabilities);
+            info!(
+                "Client {} {} requested protocol {}, using {}",
+                params.client_info.name, params.client_info.version, params.protocol_version, protocol_version
+            );
+
+            if !session.begin_initialize(protocol_version, params.client_info) {
+                return error_response(request.id, -32600, "Session is already initialized".to_string());
+            }
+
             JsonRpcResponse {
                 jsonrpc: "2.0".to_string(),
                 id: request.id,
                 result: Some(json!({
-                    "protocolVersion": "2025-06-18",
+                    "protocolVersion": protocol_version,
                     "capabilities": {
                         "tools": {}
                     },
@@ -153,7 +545,17 @@ async fn handle_request(request: JsonRpcRequest, weather_client: &WeatherClient)
                 error: None,
             }
         }
+        "ping" => {
+            JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                id: request.id,
+                result: Some(json!({})),
+                error: None,
+            }
+        }
         "initialized" => {
+            // Legacy request form of `notifications/initialized`
+            session.mark_initialized();
             JsonRpcResponse {
                 jsonrpc: "2.0".to_string(),
                 id: request.id,
@@ -165,38 +567,7 @@ async fn handle_request(request: JsonRpcRequest, weather_client: &WeatherClient)
             JsonRpcResponse {
                 jsonrpc: "2.0".to_string(),
                 id: request.id,
-                result: Some(json!({
-                    "tools": [
-                        {
-                            "name": "get_weather",
-                            "description": "Get weather forecast for GPS coordinates using YR.no API",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "latitude": {
-                                        "type": "number",
-                                        "description": "Latitude coordinate (e.g., 37.5665 for Seoul)"
-                                    },
-                                    "longitude": {
-                                        "type": "number",
-                                        "description": "Longitude coordinate (e.g., 126.9780 for Seoul)"
-                                    },
-                                    "location_name": {
-                                        "type": "string",
-                                        "description": "Optional location name for display purposes"
-                                    },
-                                    "forecast_type": {
-                                        "type": "string",
-                                        "enum": ["current", "tomorrow", "weekly"],
-                                        "description": "Type of forecast: 'current' for now, 'tomorrow' for next day, 'weekly' for 7-day forecast",
-                                        "default": "current"
-                                    }
-                                },
-                                "required": ["latitude", "longitude"]
-                            }
-                        }
-                    ]
-                })),
+                result: Some(tools::list_tools(session)),
                 error: None,
             }
         }
@@ -222,77 +593,24 @@ async fn handle_request(request: JsonRpcRequest, weather_client: &WeatherClient)
         }
         "tools/call" => {
             let params = request.params.unwrap_or(json!({}));
-            
-            if let Some(name) = params.get("name").and_then(|n| n.as_str()) {
-                if name == "get_weather" {
-                    let default_args = json!({});
-                    let arguments = params.get("arguments").unwrap_or(&default_args);
-                    
-                    let latitude = arguments.get("latitude")
-                        .and_then(|l| l.as_f64())
-                        .unwrap_or(37.5665); // Default to Seoul
-                    let longitude = arguments.get("longitude")
-                        .and_then(|l| l.as_f64())
-                        .unwrap_or(126.9780);
-                    let location_name = arguments.get("location_name")
-                        .and_then(|n| n.as_str())
-                        .unwrap_or("Unknown Location");
-                    let forecast_type = arguments.get("forecast_type")
-                        .and_then(|f| f.as_str())
-                        .unwrap_or("current");
-                    
-                    match weather_client.get_weather_by_coords(latitude, longitude, location_name, forecast_type).await {
-                        Ok(weather_info) => {
-                            JsonRpcResponse {
-                                jsonrpc: "2.0".to_string(),
-                                id: request.id,
-                                result: Some(json!({
-                                    "content": [
-                                        {
-                                            "type": "text",
-                                            "text": weather_info
-                                        }
-                                    ]
-                                })),
-                                error: None,
-                            }
-                        }
-                        Err(e) => {
-                            JsonRpcResponse {
-                                jsonrpc: "2.0".to_string(),
-                                id: request.id,
-                                result: None,
-                                error: Some(JsonRpcError {
-                                    code: -32603,
-                                    message: format!("Failed to get weather: {}", e),
-                                    data: None,
-                                }),
-                            }
-                        }
+
+            match tools::call_tool(params, weather_client, session, notifier).await {
+                Ok(result) => {
+                    JsonRpcResponse {
+                        jsonrpc: "2.0".to_string(),
+                        id: request.id,
+                        result: Some(result),
+                        error: None,
                     }
-                } else {
+                }
+                Err(error) => {
                     JsonRpcResponse {
                         jsonrpc: "2.0".to_string(),
                         id: request.id,
                         result: None,
-                        error: Some(JsonRpcError {
-                            code: -32601,
-                            message: format!("Unknown tool: {}", name),
-                            data: None,
-                        }),
+                        error: Some(error),
                     }
                 }
-            } else {
-                JsonRpcResponse {
-                    jsonrpc: "2.0".to_string(),
-                    id: request.id,
-                    result: None,
-                    error: Some(JsonRpcError {
-                        code: -32602,
-                        message: "Invalid parameters".to_string(),
-                        data: None,
-                    }),
-                }
             }
         }
         _ => {
@@ -309,3 +627,187 @@ async fn handle_request(request: JsonRpcRequest, weather_client: &WeatherClient)
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn weather_client() -> WeatherClient {
+        WeatherClient::builder().contact("tests@example.com").build().unwrap()
+    }
+
+    /// Runs `message` through the handler on `session`
+    async fn handle(message: Value, session: &Session) -> Option<Value> {
+        handle_message(message, &weather_client(), session, &Notifier::disabled()).await
+    }
+
+    fn config(json: Value) -> Config {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn flags_take_precedence_over_the_config_file() {
+        let cli = Cli::try_parse_from(["yr-weather-mcp", "--contact", "flag@example.com", "--language", "ko"]).unwrap();
+        let cli = cli.with_config(config(json!({
+            "contact": "file@example.com",
+            "base_url": "http://localhost:9000",
+            "language": "nb"
+        })));
+
+        assert_eq!(cli.contact.as_deref(), Some("flag@example.com"));
+        assert_eq!(cli.language, Some(Language::Ko));
+        // Settings without a flag come from the file
+        assert_eq!(cli.base_url.as_deref(), Some("http://localhost:9000"));
+        assert_eq!(cli.user_agent, None);
+    }
+
+    #[test]
+    fn environment_variables_take_precedence_over_the_config_file() {
+        // No other test reads this variable
+        std::env::set_var("YR_WEATHER_PROXY", "http://env-proxy:3128");
+        let cli = Cli::try_parse_from(["yr-weather-mcp"]).unwrap();
+        std::env::remove_var("YR_WEATHER_PROXY");
+
+        let cli = cli.with_config(config(json!({"proxy": "http://file-proxy:3128"})));
+        assert_eq!(cli.proxy.as_deref(), Some("http://env-proxy:3128"));
+    }
+
+    #[tokio::test]
+    async fn rejects_an_empty_batch() {
+        let response = handle(json!([]), &Session::new()).await.unwrap();
+        assert_eq!(response["id"], Value::Null);
+        assert_eq!(response["error"]["code"], -32600);
+    }
+
+    #[tokio::test]
+    async fn answers_each_request_of_a_batch() {
+        let batch = json!([
+            {"jsonrpc": "2.0", "id": 1, "method": "ping"},
+            {"jsonrpc": "2.0", "method": "notifications/initialized"},
+            {"jsonrpc": "2.0", "id": "two", "method": "ping"},
+            7
+        ]);
+        let response = handle(batch, &Session::new()).await.unwrap();
+        let responses = response.as_array().unwrap();
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0], json!({"jsonrpc": "2.0", "id": 1, "result": {}}));
+        assert_eq!(responses[1]["id"], "two");
+        assert_eq!(responses[2]["error"]["code"], -32600);
+    }
+
+    #[tokio::test]
+    async fn does_not_answer_a_batch_of_notifications() {
+        let batch = json!([
+            {"jsonrpc": "2.0", "method": "notifications/initialized"},
+            {"jsonrpc": "2.0", "method": "notifications/cancelled", "params": {"requestId": 1}}
+        ]);
+        assert_eq!(handle(batch, &Session::new()).await, None);
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_requests() {
+        let session = Session::new();
+        let cases = [
+            (json!({"jsonrpc": "1.0", "id": 1, "method": "ping"}), json!(1)),
+            (json!({"jsonrpc": "2.0", "id": 2}), json!(2)),
+            (json!({"jsonrpc": "2.0", "id": {"nested": true}, "method": "ping"}), Value::Null),
+            (json!({"jsonrpc": "2.0", "id": 3, "method": "ping", "params": "text"}), json!(3)),
+            (json!("ping"), Value::Null),
+        ];
+        for (message, id) in cases {
+            let response = handle(message.clone(), &session).await.unwrap();
+            assert_eq!(response["error"]["code"], -32600, "{}", message);
+            assert_eq!(response["id"], id, "{}", message);
+        }
+    }
+
+    #[test]
+    fn reports_malformed_json_as_a_parse_error() {
+        let error = serde_json::from_str::<Value>("{\"jsonrpc\": ").unwrap_err();
+        let response = to_json(&parse_error_response(&error)).unwrap();
+        assert_eq!(response["id"], Value::Null);
+        assert_eq!(response["error"]["code"], -32700);
+    }
+
+    fn initialize(protocol_version: &str) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": protocol_version,
+                "capabilities": {},
+                "clientInfo": {"name": "tests", "version": "1.0"}
+            }
+        })
+    }
+
+    fn call(method: &str) -> Value {
+        json!({"jsonrpc": "2.0", "id": 9, "method": method, "params": {"name": "get_weather", "arguments": {}}})
+    }
+
+    #[tokio::test]
+    async fn enforces_the_initialize_handshake() {
+        let session = Session::new();
+        for method in ["tools/call", "tools/list"] {
+            let response = handle(call(method), &session).await.unwrap();
+            assert_eq!(response["error"]["code"], -32002, "{}", method);
+        }
+        assert_eq!(handle(call("ping"), &session).await.unwrap()["result"], json!({}));
+
+        handle(initialize("2025-06-18"), &session).await.unwrap();
+        assert_eq!(session.lifecycle(), Lifecycle::Initializing);
+        let response = handle(call("tools/call"), &session).await.unwrap();
+        assert_eq!(response["error"]["code"], -32002);
+        assert!(handle(call("tools/list"), &session).await.unwrap().get("result").is_some());
+
+        let response = handle(initialize("2025-06-18"), &session).await.unwrap();
+        assert_eq!(response["error"]["code"], -32600);
+
+        handle(json!({"jsonrpc": "2.0", "method": "notifications/initialized"}), &session).await;
+        assert_eq!(session.lifecycle(), Lifecycle::Ready);
+        let response = handle(call("tools/call"), &session).await.unwrap();
+        assert_eq!(response["result"]["isError"], true);
+    }
+
+    #[test]
+    fn negotiates_a_supported_protocol_version() {
+        for version in SUPPORTED_PROTOCOL_VERSIONS {
+            assert_eq!(negotiate_protocol_version(version), *version);
+        }
+        assert_eq!(negotiate_protocol_version("2099-01-01"), SUPPORTED_PROTOCOL_VERSIONS[0]);
+        assert_eq!(negotiate_protocol_version("2024-01-01"), SUPPORTED_PROTOCOL_VERSIONS[0]);
+        assert_eq!(negotiate_protocol_version(""), SUPPORTED_PROTOCOL_VERSIONS[0]);
+    }
+
+    #[tokio::test]
+    async fn offers_structured_output_from_2025_06_18() {
+        assert!(!supports_structured_output(&Session::new()));
+        for (requested, supported) in [("2025-06-18", true), ("2025-03-26", false), ("2024-11-05", false)] {
+            let session = Session::new();
+            let response = handle(initialize(requested), &session).await.unwrap();
+            assert_eq!(response["result"]["protocolVersion"], requested);
+            assert_eq!(supports_structured_output(&session), supported, "{}", requested);
+        }
+    }
+
+    #[test]
+    fn answers_each_request_of_a_panicked_message() {
+        let single = json!({"jsonrpc": "2.0", "id": 7, "method": "tools/call"});
+        let response = internal_errors(request_ids(&single), false).unwrap();
+        assert_eq!(response["id"], 7);
+        assert_eq!(response["error"]["code"], -32603);
+
+        let batch = json!([
+            {"jsonrpc": "2.0", "id": "a", "method": "ping"},
+            {"jsonrpc": "2.0", "method": "notifications/initialized"},
+            {"jsonrpc": "2.0", "id": 2, "method": "tools/list"}
+        ]);
+        let response = internal_errors(request_ids(&batch), true).unwrap();
+        let ids: Vec<&Value> = response.as_array().unwrap().iter().map(|r| &r["id"]).collect();
+        assert_eq!(ids, [&json!("a"), &json!(2)]);
+
+        let notification = json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
+        assert_eq!(internal_errors(request_ids(&notification), false), None);
+    }
+}
@@ -0,0 +1,395 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::forecast::{self, Event, Location, Symbol, Units, Wind, ZoneInfo};
+use crate::i18n::Language;
+use crate::timezone::TimeZone;
+
+/// Lowest rate, in mm/h, counted as precipitation
+const PRECIPITATION_THRESHOLD: f64 = 0.1;
+/// met.no's nowcast steps are five minutes apart
+const STEP_SECONDS: i64 = 300;
+
+/// Radar-based precipitation for the next two hours, returned by
+/// `get_nowcast` both as Markdown text and as `structuredContent`
+#[derive(Debug, Clone, Serialize)]
+pub struct Nowcast {
+    pub location: Location,
+    /// Language of labels and the summary
+    pub language: Language,
+    /// When met.no last updated the nowcast
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+    pub units: Units,
+    /// Zone of the local times
+    pub timezone: ZoneInfo,
+    pub radar_coverage: RadarCoverage,
+    /// Conditions at the first step, when met.no provides them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current: Option<NowcastConditions>,
+    pub outlook: PrecipitationOutlook,
+    /// Precipitation rate every five minutes
+    pub steps: Vec<NowcastStep>,
+}
+
+/// Whether weather radars cover the location at the moment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RadarCoverage {
+    Ok,
+    /// Radars that cover the location are down; precipitation comes from
+    /// the weather model
+    TemporarilyUnavailable,
+    /// Inside the nowcast area but out of radar range
+    NoCoverage,
+}
+
+impl RadarCoverage {
+    /// Reads met.no's `radar_coverage` value; anything unexpected counts as
+    /// unavailable
+    pub fn from_metno(value: Option<&str>) -> Self {
+        match value {
+            Some("ok") => Self::Ok,
+            Some("no coverage") => Self::NoCoverage,
+            _ => Self::TemporarilyUnavailable,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NowcastConditions {
+    pub temperature: f64,
+    pub humidity: f64,
+    pub wind: Wind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wind_gust: Option<f64>,
+    /// Weather over the next hour
+    pub symbol: Symbol,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NowcastStep {
+    #[serde(flatten)]
+    pub at: Event,
+    /// Precipitation intensity, per hour
+    pub precipitation_rate: f64,
+}
+
+/// When precipitation starts and stops within the nowcast
+#[derive(Debug, Clone, Serialize)]
+pub struct PrecipitationOutlook {
+    pub status: OutlookStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub starts_at: Option<Event>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stops_at: Option<Event>,
+    /// Heaviest precipitation rate of any step
+    pub max_rate: f64,
+    /// One sentence in the result's language, such as "Precipitation starts
+    /// at 14:35 (in 20 min), stops at 15:10 (in 55 min)"
+    pub summary: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutlookStatus {
+    /// No precipitation at any step
+    Dry,
+    /// Dry now, precipitation later
+    Starting,
+    /// Precipitation at the first step
+    Ongoing,
+}
+
+impl PrecipitationOutlook {
+    /// Finds the first start and stop in `rates`, pairs of Unix seconds and
+    /// mm/h in time order; must not be empty
+    pub fn new(rates: &[(i64, f64)], tz: &TimeZone, language: Language) -> Self {
+        let is_wet = |&(_, rate): &(i64, f64)| rate >= PRECIPITATION_THRESHOLD;
+        let first = rates[0].0;
+        let end = rates.last().map_or(first, |&(time, _)| time) + STEP_SECONDS;
+
+        let ongoing = is_wet(&rates[0]);
+        let start = if ongoing {
+            None
+        } else {
+            rates.iter().find(|step| is_wet(step)).map(|&(time, _)| time)
+        };
+        let wet_from = if ongoing { Some(first) } else { start };
+        let stop = wet_from.and_then(|wet_from| {
+            rates
+                .iter()
+                .find(|&&(time, rate)| time > wet_from && rate < PRECIPITATION_THRESHOLD)
+                .map(|&(time, _)| time)
+        });
+
+        let local_time = |time: i64| Event::new(time, tz).local_time;
+        let minutes = |time: i64| (time - first) / 60;
+        let ending = match stop {
+            Some(stop) => language.precipitation_stops(&local_time(stop), minutes(stop)),
+            None => language.precipitation_continues(&local_time(end)),
+        };
+        let (status, summary) = match (ongoing, start) {
+            (true, _) => (
+                OutlookStatus::Ongoing,
+                format!("{}, {}", language.messages().raining_now, ending),
+            ),
+            (false, Some(start)) => (
+                OutlookStatus::Starting,
+                format!("{}, {}", language.precipitation_starts(&local_time(start), minutes(start)), ending),
+            ),
+            (false, None) => (OutlookStatus::Dry, language.dry_until(&local_time(end))),
+        };
+
+        Self {
+            status,
+            starts_at: start.map(|time| Event::new(time, tz)),
+            stops_at: stop.map(|time| Event::new(time, tz)),
+            max_rate: rates.iter().map(|&(_, rate)| rate).fold(0.0, f64::max),
+            summary,
+        }
+    }
+}
+
+impl Nowcast {
+    /// JSON Schema of the serialized nowcast, advertised as the tool's
+    /// `outputSchema`
+    pub fn output_schema() -> Value {
+        let mut step = forecast::event_schema();
+        step["properties"]["precipitation_rate"] = json!({
+            "type": "number",
+            "description": "Precipitation per hour, in the precipitation unit"
+        });
+        step["required"] = json!(["time", "local_time", "precipitation_rate"]);
+
+        json!({
+            "type": "object",
+            "properties": {
+                "location": forecast::location_schema(),
+                "language": {
+                    "type": "string",
+                    "enum": ["en", "ko", "nb"],
+                    "description": "Language of labels and the summary"
+                },
+                "updated_at": { "type": "string", "format": "date-time" },
+                "units": forecast::units_schema(),
+                "timezone": forecast::zone_info_schema(),
+                "radar_coverage": {
+                    "type": "string",
+                    "enum": ["ok", "temporarily_unavailable", "no_coverage"],
+                    "description": "Whether radar data backs the precipitation; otherwise it comes from the weather model"
+                },
+                "current": {
+                    "type": "object",
+                    "description": "Conditions now",
+                    "properties": {
+                        "temperature": { "type": "number" },
+                        "humidity": { "type": "number" },
+                        "wind": forecast::wind_schema(),
+                        "wind_gust": { "type": "number" },
+                        "symbol": forecast::symbol_schema()
+                    },
+                    "required": ["temperature", "humidity", "wind", "symbol"]
+                },
+                "outlook": {
+                    "type": "object",
+                    "properties": {
+                        "status": {
+                            "type": "string",
+                            "enum": ["dry", "starting", "ongoing"],
+                            "description": "'dry' if no step has precipitation, 'starting' if it begins later, 'ongoing' if it is falling now"
+                        },
+                        "starts_at": forecast::event_schema(),
+                        "stops_at": forecast::event_schema(),
+                        "max_rate": {
+                            "type": "number",
+                            "description": "Heaviest precipitation per hour of any step"
+                        },
+                        "summary": { "type": "string" }
+                    },
+                    "required": ["status", "max_rate", "summary"]
+                },
+                "steps": {
+                    "type": "array",
+                    "description": "Precipitation rate every five minutes",
+                    "items": step
+                }
+            },
+            "required": ["location", "language", "units", "timezone", "radar_coverage", "outlook", "steps"]
+        })
+    }
+
+    /// Converts every value from met.no's metric units into `units`
+    pub fn convert_units(mut self, units: Units) -> Self {
+        if let Some(current) = &mut self.current {
+            current.temperature = units.temperature.convert(current.temperature);
+            current.wind.speed = units.wind_speed.convert(current.wind.speed);
+            current.wind_gust = current.wind_gust.map(|v| units.wind_speed.convert(v));
+        }
+        for step in &mut self.steps {
+            step.precipitation_rate = units.precipitation.convert(step.precipitation_rate);
+        }
+        self.outlook.max_rate = units.precipitation.convert(self.outlook.max_rate);
+        self.units = units;
+        self
+    }
+
+    /// Renders the nowcast as the Markdown shown to the model
+    pub fn to_markdown(&self) -> String {
+        let m = self.language.messages();
+        let mut text = forecast::header(&format!("🌧️ **{}**", m.nowcast_title), &self.location, self.language);
+        text.push_str(&forecast::timezone_line(&self.timezone, self.language));
+
+        let icon = match self.outlook.status {
+            OutlookStatus::Dry => "🌂",
+            OutlookStatus::Starting | OutlookStatus::Ongoing => "☔",
+        };
+        text.push_str(&format!("{} **{}**\n", icon, self.outlook.summary));
+        if let Some(current) = &self.current {
+            text.push_str(&format!(
+                "🌡️ **{}:** {} · {} · 🌬️ {} {}\n",
+                m.temperature,
+                self.units.temperature.format(current.temperature),
+                current.symbol.description,
+                self.units.wind_speed.format(current.wind.speed),
+                self.language.wind_from(current.wind.compass)
+            ));
+        }
+        let radar_state = match self.radar_coverage {
+            RadarCoverage::Ok => m.radar_states[0],
+            RadarCoverage::TemporarilyUnavailable => m.radar_states[1],
+            RadarCoverage::NoCoverage => m.radar_states[2],
+        };
+        text.push_str(&format!("📡 **{}:** {}\n\n", m.radar_coverage, radar_state));
+
+        text.push_str(&format!(
+            "**{} ({}/h):**\n",
+            m.precipitation_rate,
+            self.units.precipitation.as_str()
+        ));
+        // Half an hour per line
+        for row in self.steps.chunks(6) {
+            let cells: Vec<String> = row
+                .iter()
+                .map(|step| {
+                    format!(
+                        "{} {}",
+                        step.at.local_time,
+                        self.units.precipitation.number(step.precipitation_rate)
+                    )
+                })
+                .collect();
+            text.push_str(&cells.join(" · "));
+            text.push('\n');
+        }
+
+        text.push('\n');
+        text.push_str(m.attribution);
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-05-04T12:00:00Z, 14:00 in [`zone`]
+    const START: i64 = 1_714_824_000;
+
+    fn zone() -> TimeZone {
+        TimeZone::from_name("+02:00").unwrap()
+    }
+
+    /// Five-minute steps from [`START`] with the given rates
+    fn outlook(rates: &[f64]) -> PrecipitationOutlook {
+        let rates: Vec<(i64, f64)> = rates
+            .iter()
+            .enumerate()
+            .map(|(i, &rate)| (START + i as i64 * STEP_SECONDS, rate))
+            .collect();
+        PrecipitationOutlook::new(&rates, &zone(), Language::En)
+    }
+
+    fn local_time(event: &Option<Event>) -> Option<&str> {
+        event.as_ref().map(|e| e.local_time.as_str())
+    }
+
+    #[test]
+    fn dry_outlook() {
+        let outlook = outlook(&[0.0, 0.0, 0.05, 0.0, 0.0, 0.0]);
+        assert_eq!(outlook.status, OutlookStatus::Dry);
+        assert!(outlook.starts_at.is_none() && outlook.stops_at.is_none());
+        assert_eq!(outlook.max_rate, 0.05);
+        assert_eq!(outlook.summary, "No precipitation expected before 14:30");
+    }
+
+    #[test]
+    fn precipitation_starting_later() {
+        let outlook = outlook(&[0.0, 0.0, 0.0, 0.5, 1.2, 0.3, 0.05, 0.0]);
+        assert_eq!(outlook.status, OutlookStatus::Starting);
+        assert_eq!(outlook.starts_at.as_ref().unwrap().time, "2024-05-04T12:15:00Z");
+        assert_eq!(local_time(&outlook.starts_at), Some("14:15"));
+        assert_eq!(local_time(&outlook.stops_at), Some("14:30"));
+        assert_eq!(outlook.max_rate, 1.2);
+        assert_eq!(outlook.summary, "Precipitation starts at 14:15 (in 15 min), stops at 14:30 (in 30 min)");
+    }
+
+    #[test]
+    fn precipitation_ongoing_at_the_first_step() {
+        let outlook = outlook(&[0.4, 0.4, 0.0, 0.0, 0.6]);
+        assert_eq!(outlook.status, OutlookStatus::Ongoing);
+        assert!(outlook.starts_at.is_none());
+        // Only the first stop counts, not the later shower
+        assert_eq!(local_time(&outlook.stops_at), Some("14:10"));
+        assert_eq!(outlook.summary, "Precipitation now, stops at 14:10 (in 10 min)");
+    }
+
+    #[test]
+    fn precipitation_continuing_past_the_end() {
+        let starting = outlook(&[0.0, 0.0, 0.3, 0.3]);
+        assert_eq!(starting.status, OutlookStatus::Starting);
+        assert!(starting.stops_at.is_none());
+        // The last step covers the five minutes up to 14:20
+        assert_eq!(starting.summary, "Precipitation starts at 14:10 (in 10 min), continues past 14:20");
+
+        let all_wet = outlook(&[0.2; 25]);
+        assert_eq!(all_wet.status, OutlookStatus::Ongoing);
+        assert_eq!(all_wet.summary, "Precipitation now, continues past 16:05");
+    }
+
+    #[test]
+    fn precipitation_stopping_at_the_last_step() {
+        let outlook = outlook(&[0.5, 0.5, 0.5, 0.0]);
+        assert_eq!(outlook.status, OutlookStatus::Ongoing);
+        assert_eq!(local_time(&outlook.stops_at), Some("14:15"));
+        assert_eq!(outlook.summary, "Precipitation now, stops at 14:15 (in 15 min)");
+    }
+
+    #[test]
+    fn counts_rates_from_the_threshold_as_precipitation() {
+        assert_eq!(outlook(&[0.09, 0.1]).status, OutlookStatus::Starting);
+        assert_eq!(outlook(&[0.1, 0.09]).status, OutlookStatus::Ongoing);
+    }
+
+    #[test]
+    fn summarizes_in_the_requested_language() {
+        let rates = [(START, 0.0), (START + STEP_SECONDS, 0.8)];
+        let outlook = PrecipitationOutlook::new(&rates, &zone(), Language::Ko);
+        assert_eq!(outlook.summary, "14:05에 강수 시작 (5분 후), 14:10 이후에도 계속");
+    }
+
+    #[test]
+    fn reads_met_no_radar_coverage() {
+        assert_eq!(RadarCoverage::from_metno(Some("ok")), RadarCoverage::Ok);
+        assert_eq!(RadarCoverage::from_metno(Some("no coverage")), RadarCoverage::NoCoverage);
+        assert_eq!(
+            RadarCoverage::from_metno(Some("temporarily unavailable")),
+            RadarCoverage::TemporarilyUnavailable
+        );
+        // Missing or unknown values are treated as a radar outage
+        assert_eq!(RadarCoverage::from_metno(None), RadarCoverage::TemporarilyUnavailable);
+        assert_eq!(RadarCoverage::from_metno(Some("OK")), RadarCoverage::TemporarilyUnavailable);
+
+        assert_eq!(serde_json::to_value(RadarCoverage::NoCoverage).unwrap(), "no_coverage");
+    }
+}
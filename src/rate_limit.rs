@@ -0,0 +1,165 @@
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::{sleep, Instant};
+
+/// Token bucket shared by every request sent to met.no.
+///
+/// Besides spacing out requests, the bucket can be paused when met.no asks
+/// us to back off, so concurrent tool calls all honour a `Retry-After`
+/// instead of each discovering the 429 on its own.
+pub struct RateLimiter {
+    capacity: f64,
+    per_second: f64,
+    state: Mutex<BucketState>,
+}
+
+struct BucketState {
+    tokens: f64,
+    refilled_at: Instant,
+    /// Set while met.no has asked us to stop sending requests
+    paused_until: Option<Instant>,
+}
+
+impl RateLimiter {
+    /// Creates a full bucket holding `capacity` tokens and refilling at
+    /// `per_second` tokens per second
+    pub fn new(capacity: u32, per_second: f64) -> Self {
+        Self {
+            capacity: f64::from(capacity),
+            per_second,
+            state: Mutex::new(BucketState {
+                tokens: f64::from(capacity),
+                refilled_at: Instant::now(),
+                paused_until: None,
+            }),
+        }
+    }
+
+    /// Waits until a request may be sent.
+    ///
+    /// Fails with the remaining pause if met.no asked us to back off for
+    /// longer than `max_wait`.
+    pub async fn acquire(&self, max_wait: Duration) -> Result<(), Duration> {
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let now = Instant::now();
+
+                match state.paused_until {
+                    Some(until) if until > now => {
+                        let remaining = until - now;
+                        if remaining > max_wait {
+                            return Err(remaining);
+                        }
+                        remaining
+                    }
+                    _ => {
+                        state.paused_until = None;
+                        let elapsed = now.duration_since(state.refilled_at).as_secs_f64();
+                        state.tokens = (state.tokens + elapsed * self.per_second).min(self.capacity);
+                        state.refilled_at = now;
+
+                        if state.tokens >= 1.0 {
+                            state.tokens -= 1.0;
+                            return Ok(());
+                        }
+                        Duration::from_secs_f64((1.0 - state.tokens) / self.per_second)
+                    }
+                }
+            };
+
+            sleep(wait).await;
+        }
+    }
+
+    /// Stops handing out tokens for `duration`
+    pub fn pause_for(&self, duration: Duration) {
+        let until = Instant::now() + duration;
+        let mut state = self.state.lock().unwrap();
+        if state.paused_until.is_none_or(|current| current < until) {
+            state.paused_until = Some(until);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    const MAX_WAIT: Duration = Duration::from_secs(10);
+
+    #[tokio::test(start_paused = true)]
+    async fn allows_a_burst_then_refills_steadily() {
+        let limiter = RateLimiter::new(10, 5.0);
+        let start = Instant::now();
+
+        for _ in 0..10 {
+            limiter.acquire(MAX_WAIT).await.unwrap();
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        // Once the burst is spent, requests go out every 200ms
+        limiter.acquire(MAX_WAIT).await.unwrap();
+        let first = start.elapsed();
+        limiter.acquire(MAX_WAIT).await.unwrap();
+        let second = start.elapsed();
+        assert!(first >= Duration::from_millis(199) && first <= Duration::from_millis(201), "{:?}", first);
+        assert!(second - first >= Duration::from_millis(199) && second - first <= Duration::from_millis(201));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn refills_up_to_the_capacity_only() {
+        let limiter = RateLimiter::new(10, 5.0);
+        sleep(Duration::from_secs(60)).await;
+        let start = Instant::now();
+
+        for _ in 0..10 {
+            limiter.acquire(MAX_WAIT).await.unwrap();
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        limiter.acquire(MAX_WAIT).await.unwrap();
+        assert!(start.elapsed() > Duration::ZERO);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_pause_delays_every_caller() {
+        let limiter = Arc::new(RateLimiter::new(10, 5.0));
+        let start = Instant::now();
+        limiter.pause_for(Duration::from_secs(3));
+
+        let callers: Vec<_> = (0..3)
+            .map(|_| {
+                let limiter = limiter.clone();
+                tokio::spawn(async move {
+                    limiter.acquire(MAX_WAIT).await.unwrap();
+                    start.elapsed()
+                })
+            })
+            .collect();
+        for caller in callers {
+            assert!(caller.await.unwrap() >= Duration::from_secs(3));
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_shorter_pause_does_not_cut_a_longer_one() {
+        let limiter = RateLimiter::new(10, 5.0);
+        let start = Instant::now();
+        limiter.pause_for(Duration::from_secs(5));
+        limiter.pause_for(Duration::from_secs(1));
+
+        limiter.acquire(MAX_WAIT).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_secs(5));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fails_fast_when_the_pause_exceeds_the_wait() {
+        let limiter = RateLimiter::new(10, 5.0);
+        let start = Instant::now();
+        limiter.pause_for(Duration::from_secs(30));
+
+        assert_eq!(limiter.acquire(MAX_WAIT).await, Err(Duration::from_secs(30)));
+        assert_eq!(start.elapsed(), Duration::ZERO);
+    }
+}
@@ -0,0 +1,269 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, error, info, warn};
+
+use crate::ClientInfo;
+
+/// Where a session is in the MCP initialization handshake
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Lifecycle {
+    /// No `initialize` request has been handled yet
+    #[default]
+    Uninitialized,
+    /// `initialize` was answered; waiting for `notifications/initialized`
+    Initializing,
+    /// The handshake is complete and all requests are accepted
+    Ready,
+}
+
+/// What the client told us about itself during `initialize`
+#[derive(Default)]
+struct Handshake {
+    lifecycle: Lifecycle,
+    protocol_version: Option<&'static str>,
+    client_info: Option<ClientInfo>,
+}
+
+/// What became of a request passed to [`Session::run_cancellable`]
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome<T> {
+    Completed(T),
+    /// The client cancelled the request before it completed
+    Cancelled,
+    /// Another request with the same id is still running, so this one was
+    /// not started
+    DuplicateId,
+}
+
+/// State belonging to one connected client.
+///
+/// The stdio transport has exactly one session; the HTTP transport creates
+/// one per `Mcp-Session-Id`.
+#[derive(Default)]
+pub struct Session {
+    /// Cancellation triggers for requests that are still running, keyed by
+    /// the JSON-encoded request id. A cancelled request keeps its entry,
+    /// without a trigger, until its handler has actually stopped.
+    in_flight: Mutex<HashMap<String, Option<oneshot::Sender<()>>>>,
+    handshake: Mutex<Handshake>,
+}
+
+impl Session {
+    /// Creates an empty session
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current stage of the initialization handshake
+    pub fn lifecycle(&self) -> Lifecycle {
+        self.handshake.lock().unwrap().lifecycle
+    }
+
+    /// Protocol revision agreed on during `initialize`
+    pub fn protocol_version(&self) -> Option<&'static str> {
+        self.handshake.lock().unwrap().protocol_version
+    }
+
+    /// Client name and version reported during `initialize`
+    pub fn client_info(&self) -> Option<ClientInfo> {
+        self.handshake.lock().unwrap().client_info.clone()
+    }
+
+    /// Records the outcome of `initialize`; returns `false` if the session
+    /// was already initialized
+    pub fn begin_initialize(&self, protocol_version: &'static str, client_info: ClientInfo) -> bool {
+        let mut handshake = self.handshake.lock().unwrap();
+        if handshake.lifecycle != Lifecycle::Uninitialized {
+            return false;
+        }
+
+        handshake.lifecycle = Lifecycle::Initializing;
+        handshake.protocol_version = Some(protocol_version);
+        handshake.client_info = Some(client_info);
+        true
+    }
+
+    /// Completes the handshake once the client confirms initialization
+    pub fn mark_initialized(&self) {
+        let mut handshake = self.handshake.lock().unwrap();
+        match handshake.lifecycle {
+            Lifecycle::Uninitialized => warn!("Client sent initialized before initialize"),
+            Lifecycle::Initializing => {
+                handshake.lifecycle = Lifecycle::Ready;
+                if let Some(client) = &handshake.client_info {
+                    info!("Session ready for {} {}", client.name, client.version);
+                }
+            }
+            Lifecycle::Ready => {}
+        }
+    }
+
+    /// Runs `fut` on behalf of request `id` unless a request with that id
+    /// is already running
+    pub async fn run_cancellable<F: Future>(&self, id: &Value, fut: F) -> Outcome<F::Output> {
+        let key = id.to_string();
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            if in_flight.contains_key(&key) {
+                return Outcome::DuplicateId;
+            }
+            in_flight.insert(key.clone(), Some(cancel_tx));
+        }
+        // Unregisters the request however this ends, including when the
+        // transport drops the whole future because the client went away
+        let _guard = InFlightGuard { session: self, key };
+
+        tokio::select! {
+            output = fut => Outcome::Completed(output),
+            Ok(()) = cancel_rx => Outcome::Cancelled,
+        }
+    }
+
+    /// Cancels the in-flight request `id`, if there is one
+    pub fn cancel(&self, id: &Value) {
+        let cancel_tx = self
+            .in_flight
+            .lock()
+            .unwrap()
+            .get_mut(&id.to_string())
+            .and_then(Option::take);
+        match cancel_tx {
+            Some(cancel_tx) => {
+                let _ = cancel_tx.send(());
+            }
+            None => debug!("Cancellation for unknown or finished request {}", id),
+        }
+    }
+}
+
+/// Removes a request from [`Session::in_flight`] when dropped
+struct InFlightGuard<'a> {
+    session: &'a Session,
+    key: String,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.session.in_flight.lock().unwrap().remove(&self.key);
+    }
+}
+
+/// Delivers server-to-client notifications on the channel that will also
+/// carry the response of the request being handled
+#[derive(Clone, Default)]
+pub struct Notifier {
+    tx: Option<mpsc::UnboundedSender<String>>,
+}
+
+impl Notifier {
+    /// Creates a notifier writing serialized messages to `tx`
+    pub fn new(tx: mpsc::UnboundedSender<String>) -> Self {
+        Self { tx: Some(tx) }
+    }
+
+    /// Creates a notifier that drops everything, for transports without a
+    /// return channel
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Sends a JSON-RPC notification to the client
+    pub fn notify(&self, method: &str, params: Value) {
+        let Some(tx) = &self.tx else {
+            return;
+        };
+
+        let message = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+        });
+        match serde_json::to_string(&message) {
+            Ok(message_str) => {
+                let _ = tx.send(message_str);
+            }
+            Err(e) => error!("Failed to serialize notification: {}", e),
+        }
+    }
+}
+
+/// Reports `notifications/progress` for a request that carried a
+/// `_meta.progressToken`; does nothing otherwise
+pub struct Progress {
+    token: Option<Value>,
+    notifier: Notifier,
+}
+
+impl Progress {
+    /// Creates a reporter for `token`, if the client supplied one
+    pub fn new(token: Option<Value>, notifier: Notifier) -> Self {
+        Self { token, notifier }
+    }
+
+    /// Reports that `progress` out of `total` steps are done
+    pub fn report(&self, progress: u32, total: u32, message: &str) {
+        let Some(token) = &self.token else {
+            return;
+        };
+
+        self.notifier.notify(
+            "notifications/progress",
+            json!({
+                "progressToken": token,
+                "progress": progress,
+                "total": total,
+                "message": message,
+            }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::pending;
+
+    #[tokio::test]
+    async fn completes_and_frees_the_id() {
+        let session = Session::new();
+        let id = json!(1);
+        assert_eq!(session.run_cancellable(&id, async { 7 }).await, Outcome::Completed(7));
+        assert_eq!(session.run_cancellable(&id, async { 8 }).await, Outcome::Completed(8));
+    }
+
+    #[tokio::test]
+    async fn cancels_a_running_request() {
+        let session = Session::new();
+        let id = json!("a");
+        let running = session.run_cancellable(&id, pending::<()>());
+        let cancel = async {
+            tokio::task::yield_now().await;
+            session.cancel(&id);
+        };
+        let (outcome, ()) = tokio::join!(running, cancel);
+        assert_eq!(outcome, Outcome::Cancelled);
+        assert!(session.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn rejects_a_duplicate_id_while_the_first_runs() {
+        let session = Session::new();
+        let id = json!(1);
+        let first = session.run_cancellable(&id, async {
+            tokio::task::yield_now().await;
+            "first"
+        });
+        let second = async {
+            // Let the first request register before the second arrives
+            tokio::task::yield_now().await;
+            session.run_cancellable(&id, async { "second" }).await
+        };
+        let (first, second) = tokio::join!(first, second);
+        assert_eq!(first, Outcome::Completed("first"));
+        assert_eq!(second, Outcome::DuplicateId);
+    }
+}
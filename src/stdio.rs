@@ -0,0 +1,208 @@
+use anyhow::Result;
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::io::{self, AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::{mpsc, Semaphore};
+use tokio::task::JoinSet;
+use tracing::{debug, error, info};
+
+use crate::weather::WeatherClient;
+use crate::{calls_tool, handle_guarded, parse_error_response, Notifier, Session};
+
+/// Serves line-delimited JSON-RPC over stdin/stdout.
+///
+/// Each request is handled on its own task so a slow forecast fetch doesn't
+/// hold up `ping` or `tools/list`; responses and notifications are funnelled
+/// through a single writer task so lines never interleave on stdout.
+pub async fn serve(weather_client: WeatherClient, max_in_flight: usize) -> Result<()> {
+    run(BufReader::new(io::stdin()), io::stdout(), weather_client, max_in_flight).await?;
+    info!("Server shutting down gracefully");
+    Ok(())
+}
+
+/// Reads messages from `input` until it ends and writes the replies to
+/// `output`
+async fn run<R, W>(input: R, output: W, weather_client: WeatherClient, max_in_flight: usize) -> Result<()>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let weather_client = Arc::new(weather_client);
+    let session = Arc::new(Session::new());
+    let limiter = Arc::new(Semaphore::new(max_in_flight));
+    let (tx, rx) = mpsc::unbounded_channel();
+    let writer = tokio::spawn(write_messages(rx, output));
+    let mut tasks = JoinSet::new();
+    let mut lines = input.lines();
+
+    loop {
+        // Reap finished tasks so the set doesn't grow for the whole session
+        while tasks.try_join_next().is_some() {}
+
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => {
+                info!("EOF reached, shutting down server");
+                break;
+            }
+            Err(e) => {
+                error!("Failed to read from stdin: {}", e);
+                break;
+            }
+        };
+
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        debug!("Received: {}", line);
+
+        let message: Value = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(e) => {
+                error!("Failed to parse request: {}", e);
+                let response = parse_error_response(&e);
+                match serde_json::to_string(&response) {
+                    Ok(response_str) => {
+                        let _ = tx.send(response_str);
+                    }
+                    Err(e) => error!("Failed to serialize response: {}", e),
+                }
+                continue;
+            }
+        };
+
+        // Notifications and `initialize` are cheap and change session state
+        // that later requests depend on, so finish them before reading on
+        let is_notification = message.is_object() && message.get("id").is_none();
+        let is_initialize = message.get("method").and_then(|m| m.as_str()) == Some("initialize");
+        if is_notification || is_initialize {
+            let notifier = Notifier::new(tx.clone());
+            if let Some(response) = handle_guarded(message, &weather_client, &session, &notifier).await {
+                let _ = tx.send(response.to_string());
+            }
+            continue;
+        }
+
+        // Tool calls wait for a slot on their own task, so the reader keeps
+        // seeing cancellations and pings while the limit is reached
+        let limiter = calls_tool(&message).then(|| limiter.clone());
+        let weather_client = weather_client.clone();
+        let session = session.clone();
+        let tx = tx.clone();
+
+        tasks.spawn(async move {
+            let permit = match limiter {
+                Some(limiter) => match limiter.acquire_owned().await {
+                    Ok(permit) => Some(permit),
+                    Err(_) => return,
+                },
+                None => None,
+            };
+            let notifier = Notifier::new(tx.clone());
+            let response = handle_guarded(message, &weather_client, &session, &notifier).await;
+            drop(permit);
+
+            // Don't send response for notifications
+            match response {
+                Some(response) => {
+                    let _ = tx.send(response.to_string());
+                }
+                None => debug!("No response to send"),
+            }
+        });
+    }
+
+    // Let in-flight requests finish before closing stdout
+    while tasks.join_next().await.is_some() {}
+    drop(tx);
+    writer.await?
+}
+
+/// Writes queued messages to `output`, one per line
+async fn write_messages<W: AsyncWrite + Unpin>(mut rx: mpsc::UnboundedReceiver<String>, mut output: W) -> Result<()> {
+    while let Some(message) = rx.recv().await {
+        debug!("Sending: {}", message);
+        output.write_all(message.as_bytes()).await?;
+        output.write_all(b"\n").await?;
+        output.flush().await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::time::Duration;
+    use tokio::io::duplex;
+    use tokio::net::TcpListener;
+    use tokio::time::timeout;
+
+    /// A client whose met.no accepts connections but never answers
+    async fn stalled_client() -> WeatherClient {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut connections = Vec::new();
+            while let Ok((connection, _)) = listener.accept().await {
+                connections.push(connection);
+            }
+        });
+        WeatherClient::builder()
+            .base_url(base_url)
+            .contact("tests@example.com")
+            .request_timeout(Duration::from_secs(60))
+            .max_retries(0)
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn keeps_reading_while_tool_calls_fill_every_slot() {
+        let (mut input, server_input) = duplex(64 * 1024);
+        let (server_output, output) = duplex(64 * 1024);
+        let server = tokio::spawn(run(BufReader::new(server_input), server_output, stalled_client().await, 1));
+        let mut replies = BufReader::new(output).lines();
+
+        let messages = [
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "initialize",
+                "params": {
+                    "protocolVersion": "2025-06-18",
+                    "capabilities": {},
+                    "clientInfo": {"name": "tests", "version": "1.0"}
+                }
+            }),
+            json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+            json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "method": "tools/call",
+                "params": {"name": "get_weather", "arguments": {"latitude": 59.91, "longitude": 10.75}}
+            }),
+            json!({"jsonrpc": "2.0", "id": 3, "method": "ping"}),
+        ];
+        for message in messages {
+            input.write_all(format!("{}\n", message).as_bytes()).await.unwrap();
+        }
+
+        let reply = |line: Option<String>| serde_json::from_str::<Value>(&line.unwrap()).unwrap();
+        let wait = Duration::from_secs(5);
+        assert_eq!(reply(timeout(wait, replies.next_line()).await.unwrap().unwrap())["id"], 1);
+        // The stalled call holds the only slot, yet the ping is answered
+        assert_eq!(reply(timeout(wait, replies.next_line()).await.unwrap().unwrap())["id"], 3);
+
+        let cancel = json!({"jsonrpc": "2.0", "method": "notifications/cancelled", "params": {"requestId": 2}});
+        input.write_all(format!("{}\n", cancel).as_bytes()).await.unwrap();
+        drop(input);
+
+        // The cancelled call ends without a reply, letting the server stop
+        timeout(wait, server).await.unwrap().unwrap().unwrap();
+        assert_eq!(replies.next_line().await.unwrap(), None);
+    }
+}
@@ -0,0 +1,465 @@
+use jiff::ToSpan;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::astronomy::{MoonTimes, SunTimes, SUN_HORIZON};
+use crate::forecast::{self, Event, Location, ZoneInfo};
+use crate::i18n::Language;
+use crate::timezone::{Date, TimeZone};
+
+/// Sun and moon times for one location, returned by `get_sun_moon` both
+/// as Markdown text and as `structuredContent`
+#[derive(Debug, Clone, Serialize)]
+pub struct SunMoon {
+    pub location: Location,
+    /// Language of labels and descriptions
+    pub language: Language,
+    /// Zone whose calendar days the times are grouped by
+    pub timezone: ZoneInfo,
+    pub days: Vec<SunMoonDay>,
+}
+
+/// Sun and moon over one local calendar day
+#[derive(Debug, Clone, Serialize)]
+pub struct SunMoonDay {
+    /// Human-readable label such as "Today" or "Sat 18 Oct"
+    pub label: String,
+    /// Local calendar date (YYYY-MM-DD)
+    pub date: String,
+    #[serde(flatten)]
+    pub events: SunMoonEvents,
+}
+
+/// Rise and set times, culmination and moon phase of one local day
+#[derive(Debug, Clone, Serialize)]
+pub struct SunMoonEvents {
+    /// Where the times come from
+    pub source: AstroSource,
+    pub sun: Sun,
+    pub moon: Moon,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AstroSource {
+    /// met.no's Sunrise API
+    MetNo,
+    /// Computed locally because met.no could not be reached
+    Calculated,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Sun {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sunrise: Option<Event>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sunset: Option<Event>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub solar_noon: Option<Event>,
+    /// Elevation of the sun's centre at solar noon, in degrees
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub noon_elevation: Option<f64>,
+    /// Minutes of the day the sun is up
+    pub daylight_minutes: u32,
+    /// Set when the sun neither rises nor sets all day
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub polar: Option<Polar>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Polar {
+    MidnightSun,
+    PolarNight,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Moon {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub moonrise: Option<Event>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub moonset: Option<Event>,
+    /// Degrees the moon is ahead of the sun: 0 at new moon, 90 at first
+    /// quarter, 180 at full moon and 270 at last quarter
+    pub phase_angle: f64,
+    pub phase: MoonPhase,
+    /// Lit share of the disc, in percent
+    pub illumination: f64,
+}
+
+/// One of the eight named phases, each spanning 45° of phase angle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MoonPhase {
+    NewMoon,
+    WaxingCrescent,
+    FirstQuarter,
+    WaxingGibbous,
+    FullMoon,
+    WaningGibbous,
+    LastQuarter,
+    WaningCrescent,
+}
+
+impl MoonPhase {
+    const ALL: [MoonPhase; 8] = [
+        Self::NewMoon,
+        Self::WaxingCrescent,
+        Self::FirstQuarter,
+        Self::WaxingGibbous,
+        Self::FullMoon,
+        Self::WaningGibbous,
+        Self::LastQuarter,
+        Self::WaningCrescent,
+    ];
+
+    /// The phase a phase angle in degrees falls into
+    fn from_angle(angle: f64) -> Self {
+        Self::ALL[((angle.rem_euclid(360.0) + 22.5) / 45.0) as usize % 8]
+    }
+
+    /// Name in the given language
+    fn label(self, language: Language) -> &'static str {
+        language.messages().moon_phases[self as usize]
+    }
+
+    /// As seen from the northern hemisphere
+    fn icon(self) -> &'static str {
+        match self {
+            Self::NewMoon => "🌑",
+            Self::WaxingCrescent => "🌒",
+            Self::FirstQuarter => "🌓",
+            Self::WaxingGibbous => "🌔",
+            Self::FullMoon => "🌕",
+            Self::WaningGibbous => "🌖",
+            Self::LastQuarter => "🌗",
+            Self::WaningCrescent => "🌘",
+        }
+    }
+}
+
+impl SunMoonEvents {
+    /// Describes the sun and moon over the local day `date`
+    pub fn new(source: AstroSource, sun: SunTimes, moon: MoonTimes, date: Date, tz: &TimeZone) -> Self {
+        let (start, end) = (tz.timestamp(date, 0), tz.timestamp(date.saturating_add(1.day()), 0));
+        // Without rise or set, whether the sun is up at noon tells midnight
+        // sun from polar night; with no elevation either, it stays unknown
+        let polar = match (sun.sunrise, sun.sunset, sun.noon_elevation) {
+            (None, None, Some(elevation)) if elevation > SUN_HORIZON => Some(Polar::MidnightSun),
+            (None, None, Some(_)) => Some(Polar::PolarNight),
+            _ => None,
+        };
+        let daylight = match (sun.sunrise, sun.sunset) {
+            (Some(rise), Some(set)) if set > rise => set - rise,
+            // Sets in the morning and rises again in the evening, as around
+            // the end of the midnight sun season
+            (Some(rise), Some(set)) => set - start + end - rise,
+            (Some(rise), None) => end - rise,
+            (None, Some(set)) => set - start,
+            (None, None) if polar == Some(Polar::MidnightSun) => end - start,
+            (None, None) => 0,
+        };
+
+        Self {
+            source,
+            sun: Sun {
+                sunrise: sun.sunrise.map(|t| Event::new(t, tz)),
+                sunset: sun.sunset.map(|t| Event::new(t, tz)),
+                solar_noon: sun.solar_noon.map(|t| Event::new(t, tz)),
+                noon_elevation: sun.noon_elevation,
+                daylight_minutes: ((daylight + 30) / 60) as u32,
+                polar,
+            },
+            moon: Moon {
+                moonrise: moon.moonrise.map(|t| Event::new(t, tz)),
+                moonset: moon.moonset.map(|t| Event::new(t, tz)),
+                phase_angle: moon.phase,
+                phase: MoonPhase::from_angle(moon.phase),
+                illumination: ((1.0 - moon.phase.to_radians().cos()) * 50.0).round(),
+            },
+        }
+    }
+
+    /// One-line summary for the days of a forecast
+    pub fn summary_markdown(&self, language: Language) -> String {
+        let m = language.messages();
+        let sun = match self.sun.polar {
+            Some(Polar::MidnightSun) => format!("🌞 {}", m.midnight_sun),
+            Some(Polar::PolarNight) => format!("🌌 {}", m.polar_night),
+            None => format!(
+                "🌅 {} · 🌇 {} · ⏳ {}",
+                local_time(&self.sun.sunrise),
+                local_time(&self.sun.sunset),
+                language.duration(self.sun.daylight_minutes)
+            ),
+        };
+        format!(
+            "{} · {} {} ({:.0}%)",
+            sun,
+            self.moon.phase.icon(),
+            self.moon.phase.label(language),
+            self.moon.illumination
+        )
+    }
+
+    /// Lines of the `get_sun_moon` result for one day
+    fn markdown(&self, language: Language) -> String {
+        let m = language.messages();
+        let mut lines = match self.sun.polar {
+            Some(Polar::MidnightSun) => format!("🌞 {}\n", m.midnight_sun),
+            Some(Polar::PolarNight) => format!("🌌 {}\n", m.polar_night),
+            None => format!(
+                "🌅 **{}:** {} · 🌇 **{}:** {}\n",
+                m.sunrise,
+                local_time(&self.sun.sunrise),
+                m.sunset,
+                local_time(&self.sun.sunset)
+            ),
+        };
+        if let Some(noon) = &self.sun.solar_noon {
+            lines.push_str(&format!("☀️ **{}:** {}", m.solar_noon, noon.local_time));
+            if let Some(elevation) = self.sun.noon_elevation {
+                lines.push_str(&format!(" ({:.1}°)", elevation));
+            }
+            lines.push('\n');
+        }
+        lines.push_str(&format!(
+            "⏳ **{}:** {}\n",
+            m.daylight,
+            language.duration(self.sun.daylight_minutes)
+        ));
+        lines.push_str(&format!(
+            "🌙 **{}:** {} · **{}:** {}\n",
+            m.moonrise,
+            local_time(&self.moon.moonrise),
+            m.moonset,
+            local_time(&self.moon.moonset)
+        ));
+        lines.push_str(&format!(
+            "{} {} ({:.0}%)\n",
+            self.moon.phase.icon(),
+            self.moon.phase.label(language),
+            self.moon.illumination
+        ));
+        lines
+    }
+}
+
+impl SunMoon {
+    /// JSON Schema of the serialized result, advertised as the tool's
+    /// `outputSchema`
+    pub fn output_schema() -> Value {
+        let mut day = events_schema();
+        day["properties"]["label"] = json!({ "type": "string" });
+        day["properties"]["date"] = json!({
+            "type": "string",
+            "format": "date",
+            "description": "Local calendar date"
+        });
+        day["required"] = json!(["label", "date", "source", "sun", "moon"]);
+
+        json!({
+            "type": "object",
+            "properties": {
+                "location": forecast::location_schema(),
+                "language": {
+                    "type": "string",
+                    "enum": ["en", "ko", "nb"],
+                    "description": "Language of labels"
+                },
+                "timezone": forecast::zone_info_schema(),
+                "days": {
+                    "type": "array",
+                    "items": day
+                }
+            },
+            "required": ["location", "language", "timezone", "days"]
+        })
+    }
+
+    /// Renders the result as the Markdown shown to the model
+    pub fn to_markdown(&self) -> String {
+        let m = self.language.messages();
+        let mut text = forecast::header(&format!("🌗 **{}**", m.sun_moon_title), &self.location, self.language);
+        text.push_str(&forecast::timezone_line(&self.timezone, self.language));
+
+        for day in &self.days {
+            text.push_str(&format!("**{}** ({})\n", day.label, day.date));
+            text.push_str(&day.events.markdown(self.language));
+            text.push('\n');
+        }
+
+        text.push_str(&offline_note(self.days.iter().map(|day| &day.events), self.language));
+        text.push_str(m.attribution);
+        text
+    }
+}
+
+/// JSON Schema of [`SunMoonEvents`]
+pub fn events_schema() -> Value {
+    let event = forecast::event_schema();
+    json!({
+        "type": "object",
+        "properties": {
+            "source": {
+                "type": "string",
+                "enum": ["met_no", "calculated"],
+                "description": "'met_no' for met.no's Sunrise API, 'calculated' when computed offline because it could not be reached"
+            },
+            "sun": {
+                "type": "object",
+                "properties": {
+                    "sunrise": event,
+                    "sunset": event,
+                    "solar_noon": event,
+                    "noon_elevation": {
+                        "type": "number",
+                        "description": "Elevation of the sun at solar noon in degrees"
+                    },
+                    "daylight_minutes": { "type": "integer", "minimum": 0 },
+                    "polar": {
+                        "type": "string",
+                        "enum": ["midnight_sun", "polar_night"],
+                        "description": "Set when the sun neither rises nor sets that day"
+                    }
+                },
+                "required": ["daylight_minutes"]
+            },
+            "moon": {
+                "type": "object",
+                "properties": {
+                    "moonrise": event,
+                    "moonset": event,
+                    "phase_angle": {
+                        "type": "number",
+                        "description": "0 new moon, 90 first quarter, 180 full moon, 270 last quarter"
+                    },
+                    "phase": {
+                        "type": "string",
+                        "enum": [
+                            "new_moon", "waxing_crescent", "first_quarter", "waxing_gibbous",
+                            "full_moon", "waning_gibbous", "last_quarter", "waning_crescent"
+                        ]
+                    },
+                    "illumination": {
+                        "type": "number",
+                        "description": "Percent of the disc that is lit"
+                    }
+                },
+                "required": ["phase_angle", "phase", "illumination"]
+            }
+        },
+        "required": ["source", "sun", "moon"]
+    })
+}
+
+/// Line noting that some of the times were calculated offline, followed
+/// by a blank line; empty when met.no provided all of them
+pub fn offline_note<'a>(events: impl IntoIterator<Item = &'a SunMoonEvents>, language: Language) -> String {
+    if events.into_iter().any(|e| e.source == AstroSource::Calculated) {
+        format!("*{}*\n\n", language.messages().calculated_offline)
+    } else {
+        String::new()
+    }
+}
+
+/// Local clock time of an event, or a dash when it does not happen that day
+fn local_time(event: &Option<Event>) -> &str {
+    event.as_ref().map_or("–", |e| e.local_time.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astronomy;
+
+    const NO_MOON: MoonTimes = MoonTimes { moonrise: None, moonset: None, phase: 0.0 };
+
+    fn date() -> Date {
+        "2024-06-21".parse::<Date>().unwrap()
+    }
+
+    fn sun(sunrise: Option<u32>, sunset: Option<u32>, noon_elevation: Option<f64>) -> SunTimes {
+        let tz = TimeZone::utc();
+        let at = |hour: u32| tz.timestamp(date(), hour * 3600);
+        SunTimes {
+            sunrise: sunrise.map(at),
+            sunset: sunset.map(at),
+            solar_noon: Some(at(12)),
+            noon_elevation,
+        }
+    }
+
+    fn events(sun: SunTimes) -> Sun {
+        SunMoonEvents::new(AstroSource::Calculated, sun, NO_MOON, date(), &TimeZone::utc()).sun
+    }
+
+    #[test]
+    fn counts_daylight_between_rise_and_set() {
+        let day = events(sun(Some(6), Some(18), Some(40.0)));
+        assert_eq!(day.daylight_minutes, 12 * 60);
+        assert_eq!(day.polar, None);
+    }
+
+    #[test]
+    fn counts_daylight_around_a_set_before_the_rise() {
+        let day = events(sun(Some(23), Some(1), Some(40.0)));
+        assert_eq!(day.daylight_minutes, 2 * 60);
+        assert_eq!(day.polar, None);
+    }
+
+    #[test]
+    fn tells_midnight_sun_from_polar_night_by_the_noon_elevation() {
+        let midnight_sun = events(sun(None, None, Some(5.0)));
+        assert_eq!(midnight_sun.polar, Some(Polar::MidnightSun));
+        assert_eq!(midnight_sun.daylight_minutes, 24 * 60);
+
+        let polar_night = events(sun(None, None, Some(-3.0)));
+        assert_eq!(polar_night.polar, Some(Polar::PolarNight));
+        assert_eq!(polar_night.daylight_minutes, 0);
+    }
+
+    #[test]
+    fn leaves_the_polar_state_unknown_without_an_elevation() {
+        let day = events(sun(None, None, None));
+        assert_eq!(day.polar, None);
+        assert_eq!(day.daylight_minutes, 0);
+    }
+
+    #[test]
+    fn classifies_tromso_from_the_offline_calculation() {
+        let tz = TimeZone::from_name("Europe/Oslo").unwrap();
+        let day = |date: &str| {
+            let date = date.parse::<Date>().unwrap();
+            let sun = astronomy::sun_times(69.6492, 18.9553, date, &tz);
+            SunMoonEvents::new(AstroSource::Calculated, sun, NO_MOON, date, &tz).sun
+        };
+        let june = day("2024-06-21");
+        assert_eq!(june.polar, Some(Polar::MidnightSun));
+        assert_eq!(june.daylight_minutes, 24 * 60);
+        let december = day("2024-12-21");
+        assert_eq!(december.polar, Some(Polar::PolarNight));
+        assert_eq!(december.daylight_minutes, 0);
+    }
+
+    #[test]
+    fn names_moon_phases_and_illumination() {
+        let moon = |phase: f64| {
+            let moon = MoonTimes { phase, ..NO_MOON };
+            SunMoonEvents::new(AstroSource::MetNo, sun(Some(6), Some(18), None), moon, date(), &TimeZone::utc()).moon
+        };
+        let cases = [
+            (0.0, MoonPhase::NewMoon, 0.0),
+            (350.0, MoonPhase::NewMoon, 1.0),
+            (90.0, MoonPhase::FirstQuarter, 50.0),
+            (180.0, MoonPhase::FullMoon, 100.0),
+            (200.0, MoonPhase::FullMoon, 97.0),
+            (270.0, MoonPhase::LastQuarter, 50.0),
+            (315.0, MoonPhase::WaningCrescent, 15.0),
+        ];
+        for (angle, phase, illumination) in cases {
+            let moon = moon(angle);
+            assert_eq!((moon.phase, moon.illumination), (phase, illumination), "angle {}", angle);
+        }
+    }
+}
@@ -0,0 +1,361 @@
+use serde::Serialize;
+
+/// Weather shown by a met.no symbol, from the official symbol table at
+/// <https://github.com/metno/weathericons>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeatherSymbol {
+    ClearSky,
+    Fair,
+    PartlyCloudy,
+    Cloudy,
+    Fog,
+    LightRainShowers,
+    RainShowers,
+    HeavyRainShowers,
+    LightRainShowersAndThunder,
+    RainShowersAndThunder,
+    HeavyRainShowersAndThunder,
+    LightSleetShowers,
+    SleetShowers,
+    HeavySleetShowers,
+    LightSleetShowersAndThunder,
+    SleetShowersAndThunder,
+    HeavySleetShowersAndThunder,
+    LightSnowShowers,
+    SnowShowers,
+    HeavySnowShowers,
+    LightSnowShowersAndThunder,
+    SnowShowersAndThunder,
+    HeavySnowShowersAndThunder,
+    LightRain,
+    Rain,
+    HeavyRain,
+    LightRainAndThunder,
+    RainAndThunder,
+    HeavyRainAndThunder,
+    LightSleet,
+    Sleet,
+    HeavySleet,
+    LightSleetAndThunder,
+    SleetAndThunder,
+    HeavySleetAndThunder,
+    LightSnow,
+    Snow,
+    HeavySnow,
+    LightSnowAndThunder,
+    SnowAndThunder,
+    HeavySnowAndThunder,
+}
+
+/// Time-of-day variant of a symbol, given as a `_day`, `_night` or
+/// `_polartwilight` suffix on the code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Variant {
+    Day,
+    Night,
+    /// Sun just below the horizon all day, in polar regions
+    PolarTwilight,
+}
+
+impl Variant {
+    pub const ALL: [Variant; 3] = [Self::Day, Self::Night, Self::PolarTwilight];
+
+    /// Code suffix without the underscore
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Day => "day",
+            Self::Night => "night",
+            Self::PolarTwilight => "polartwilight",
+        }
+    }
+}
+
+impl WeatherSymbol {
+    pub const ALL: [WeatherSymbol; 41] = [
+        Self::ClearSky,
+        Self::Fair,
+        Self::PartlyCloudy,
+        Self::Cloudy,
+        Self::Fog,
+        Self::LightRainShowers,
+        Self::RainShowers,
+        Self::HeavyRainShowers,
+        Self::LightRainShowersAndThunder,
+        Self::RainShowersAndThunder,
+        Self::HeavyRainShowersAndThunder,
+        Self::LightSleetShowers,
+        Self::SleetShowers,
+        Self::HeavySleetShowers,
+        Self::LightSleetShowersAndThunder,
+        Self::SleetShowersAndThunder,
+        Self::HeavySleetShowersAndThunder,
+        Self::LightSnowShowers,
+        Self::SnowShowers,
+        Self::HeavySnowShowers,
+        Self::LightSnowShowersAndThunder,
+        Self::SnowShowersAndThunder,
+        Self::HeavySnowShowersAndThunder,
+        Self::LightRain,
+        Self::Rain,
+        Self::HeavyRain,
+        Self::LightRainAndThunder,
+        Self::RainAndThunder,
+        Self::HeavyRainAndThunder,
+        Self::LightSleet,
+        Self::Sleet,
+        Self::HeavySleet,
+        Self::LightSleetAndThunder,
+        Self::SleetAndThunder,
+        Self::HeavySleetAndThunder,
+        Self::LightSnow,
+        Self::Snow,
+        Self::HeavySnow,
+        Self::LightSnowAndThunder,
+        Self::SnowAndThunder,
+        Self::HeavySnowAndThunder,
+    ];
+
+    /// met.no code without the variant suffix.
+    ///
+    /// Two codes keep the misspelling of the official table
+    /// (`lightssleet...`, `lightssnow...`), since that is what the API sends.
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::ClearSky => "clearsky",
+            Self::Fair => "fair",
+            Self::PartlyCloudy => "partlycloudy",
+            Self::Cloudy => "cloudy",
+            Self::Fog => "fog",
+            Self::LightRainShowers => "lightrainshowers",
+            Self::RainShowers => "rainshowers",
+            Self::HeavyRainShowers => "heavyrainshowers",
+            Self::LightRainShowersAndThunder => "lightrainshowersandthunder",
+            Self::RainShowersAndThunder => "rainshowersandthunder",
+            Self::HeavyRainShowersAndThunder => "heavyrainshowersandthunder",
+            Self::LightSleetShowers => "lightsleetshowers",
+            Self::SleetShowers => "sleetshowers",
+            Self::HeavySleetShowers => "heavysleetshowers",
+            Self::LightSleetShowersAndThunder => "lightssleetshowersandthunder",
+            Self::SleetShowersAndThunder => "sleetshowersandthunder",
+            Self::HeavySleetShowersAndThunder => "heavysleetshowersandthunder",
+            Self::LightSnowShowers => "lightsnowshowers",
+            Self::SnowShowers => "snowshowers",
+            Self::HeavySnowShowers => "heavysnowshowers",
+            Self::LightSnowShowersAndThunder => "lightssnowshowersandthunder",
+            Self::SnowShowersAndThunder => "snowshowersandthunder",
+            Self::HeavySnowShowersAndThunder => "heavysnowshowersandthunder",
+            Self::LightRain => "lightrain",
+            Self::Rain => "rain",
+            Self::HeavyRain => "heavyrain",
+            Self::LightRainAndThunder => "lightrainandthunder",
+            Self::RainAndThunder => "rainandthunder",
+            Self::HeavyRainAndThunder => "heavyrainandthunder",
+            Self::LightSleet => "lightsleet",
+            Self::Sleet => "sleet",
+            Self::HeavySleet => "heavysleet",
+            Self::LightSleetAndThunder => "lightsleetandthunder",
+            Self::SleetAndThunder => "sleetandthunder",
+            Self::HeavySleetAndThunder => "heavysleetandthunder",
+            Self::LightSnow => "lightsnow",
+            Self::Snow => "snow",
+            Self::HeavySnow => "heavysnow",
+            Self::LightSnowAndThunder => "lightsnowandthunder",
+            Self::SnowAndThunder => "snowandthunder",
+            Self::HeavySnowAndThunder => "heavysnowandthunder",
+        }
+    }
+
+    /// English name, as in met.no's symbol legend
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ClearSky => "Clear sky",
+            Self::Fair => "Fair",
+            Self::PartlyCloudy => "Partly cloudy",
+            Self::Cloudy => "Cloudy",
+            Self::Fog => "Fog",
+            Self::LightRainShowers => "Light rain showers",
+            Self::RainShowers => "Rain showers",
+            Self::HeavyRainShowers => "Heavy rain showers",
+            Self::LightRainShowersAndThunder => "Light rain showers and thunder",
+            Self::RainShowersAndThunder => "Rain showers and thunder",
+            Self::HeavyRainShowersAndThunder => "Heavy rain showers and thunder",
+            Self::LightSleetShowers => "Light sleet showers",
+            Self::SleetShowers => "Sleet showers",
+            Self::HeavySleetShowers => "Heavy sleet showers",
+            Self::LightSleetShowersAndThunder => "Light sleet showers and thunder",
+            Self::SleetShowersAndThunder => "Sleet showers and thunder",
+            Self::HeavySleetShowersAndThunder => "Heavy sleet showers and thunder",
+            Self::LightSnowShowers => "Light snow showers",
+            Self::SnowShowers => "Snow showers",
+            Self::HeavySnowShowers => "Heavy snow showers",
+            Self::LightSnowShowersAndThunder => "Light snow showers and thunder",
+            Self::SnowShowersAndThunder => "Snow showers and thunder",
+            Self::HeavySnowShowersAndThunder => "Heavy snow showers and thunder",
+            Self::LightRain => "Light rain",
+            Self::Rain => "Rain",
+            Self::HeavyRain => "Heavy rain",
+            Self::LightRainAndThunder => "Light rain and thunder",
+            Self::RainAndThunder => "Rain and thunder",
+            Self::HeavyRainAndThunder => "Heavy rain and thunder",
+            Self::LightSleet => "Light sleet",
+            Self::Sleet => "Sleet",
+            Self::HeavySleet => "Heavy sleet",
+            Self::LightSleetAndThunder => "Light sleet and thunder",
+            Self::SleetAndThunder => "Sleet and thunder",
+            Self::HeavySleetAndThunder => "Heavy sleet and thunder",
+            Self::LightSnow => "Light snow",
+            Self::Snow => "Snow",
+            Self::HeavySnow => "Heavy snow",
+            Self::LightSnowAndThunder => "Light snow and thunder",
+            Self::SnowAndThunder => "Snow and thunder",
+            Self::HeavySnowAndThunder => "Heavy snow and thunder",
+        }
+    }
+
+    /// Whether met.no publishes day, night and polar twilight variants;
+    /// only symbols where the sun is visible do
+    pub fn has_variants(self) -> bool {
+        matches!(
+            self,
+            Self::ClearSky
+                | Self::Fair
+                | Self::PartlyCloudy
+                | Self::LightRainShowers
+                | Self::RainShowers
+                | Self::HeavyRainShowers
+                | Self::LightRainShowersAndThunder
+                | Self::RainShowersAndThunder
+                | Self::HeavyRainShowersAndThunder
+                | Self::LightSleetShowers
+                | Self::SleetShowers
+                | Self::HeavySleetShowers
+                | Self::LightSleetShowersAndThunder
+                | Self::SleetShowersAndThunder
+                | Self::HeavySleetShowersAndThunder
+                | Self::LightSnowShowers
+                | Self::SnowShowers
+                | Self::HeavySnowShowers
+                | Self::LightSnowShowersAndThunder
+                | Self::SnowShowersAndThunder
+                | Self::HeavySnowShowersAndThunder
+        )
+    }
+
+    /// Emoji shown next to the name
+    pub fn icon(self, variant: Option<Variant>) -> &'static str {
+        let night = variant == Some(Variant::Night);
+        match self {
+            Self::ClearSky | Self::Fair if night => "🌙",
+            Self::ClearSky => "☀️",
+            Self::Fair => "🌤️",
+            Self::PartlyCloudy => "⛅",
+            Self::Cloudy => "☁️",
+            Self::Fog => "🌫️",
+            Self::LightRainShowers | Self::RainShowers | Self::HeavyRainShowers if night => "🌧️",
+            Self::LightRainShowers | Self::RainShowers | Self::HeavyRainShowers => "🌦️",
+            Self::LightRain => "🌦️",
+            Self::Rain | Self::HeavyRain => "🌧️",
+            Self::LightSleetShowers
+            | Self::SleetShowers
+            | Self::HeavySleetShowers
+            | Self::LightSleet
+            | Self::Sleet
+            | Self::HeavySleet => "🌧️❄️",
+            Self::LightSnowShowers | Self::SnowShowers | Self::LightSnow => "🌨️",
+            Self::Snow => "❄️",
+            Self::HeavySnowShowers | Self::HeavySnow => "🌨️❄️",
+            Self::LightRainShowersAndThunder
+            | Self::RainShowersAndThunder
+            | Self::HeavyRainShowersAndThunder
+            | Self::LightRainAndThunder
+            | Self::RainAndThunder
+            | Self::HeavyRainAndThunder => "⛈️",
+            Self::LightSleetShowersAndThunder
+            | Self::SleetShowersAndThunder
+            | Self::HeavySleetShowersAndThunder
+            | Self::LightSnowShowersAndThunder
+            | Self::SnowShowersAndThunder
+            | Self::HeavySnowShowersAndThunder
+            | Self::LightSleetAndThunder
+            | Self::SleetAndThunder
+            | Self::HeavySleetAndThunder
+            | Self::LightSnowAndThunder
+            | Self::SnowAndThunder
+            | Self::HeavySnowAndThunder => "🌩️",
+        }
+    }
+
+    /// Splits a full code such as `rainshowers_polartwilight` into symbol
+    /// and variant; `None` for codes outside the table, or with a variant
+    /// suffix the symbol does not have
+    pub fn parse(code: &str) -> Option<(Self, Option<Variant>)> {
+        let (base, suffix) = match code.split_once('_') {
+            Some((base, suffix)) => (base, Some(suffix)),
+            None => (code, None),
+        };
+        // Accept the correctly spelled forms of the two misspelled codes too
+        let symbol = Self::ALL.into_iter().find(|s| {
+            s.code() == base || (s.code().starts_with("lightss") && s.code().replacen("lightss", "lights", 1) == base)
+        })?;
+
+        match suffix {
+            None => Some((symbol, None)),
+            Some(suffix) if symbol.has_variants() => {
+                let variant = Variant::ALL.into_iter().find(|v| v.as_str() == suffix)?;
+                Some((symbol, Some(variant)))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_code_parses_back_to_its_symbol() {
+        for symbol in WeatherSymbol::ALL {
+            assert_eq!(WeatherSymbol::parse(symbol.code()), Some((symbol, None)), "{}", symbol.code());
+            for variant in Variant::ALL {
+                let code = format!("{}_{}", symbol.code(), variant.as_str());
+                let expected = symbol.has_variants().then_some((symbol, Some(variant)));
+                assert_eq!(WeatherSymbol::parse(&code), expected, "{}", code);
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_met_no_misspelled_codes() {
+        assert_eq!(
+            WeatherSymbol::parse("lightssleetshowersandthunder_day"),
+            Some((WeatherSymbol::LightSleetShowersAndThunder, Some(Variant::Day)))
+        );
+        assert_eq!(
+            WeatherSymbol::parse("lightssnowshowersandthunder_polartwilight"),
+            Some((WeatherSymbol::LightSnowShowersAndThunder, Some(Variant::PolarTwilight)))
+        );
+        // The correct spellings are accepted too
+        assert_eq!(
+            WeatherSymbol::parse("lightsnowshowersandthunder_night"),
+            Some((WeatherSymbol::LightSnowShowersAndThunder, Some(Variant::Night)))
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_codes_and_variants() {
+        assert_eq!(WeatherSymbol::parse("cloudy_day"), None);
+        assert_eq!(WeatherSymbol::parse("rain_night"), None);
+        assert_eq!(WeatherSymbol::parse("clearsky_evening"), None);
+        assert_eq!(WeatherSymbol::parse("hail"), None);
+        assert_eq!(WeatherSymbol::parse(""), None);
+    }
+
+    #[test]
+    fn night_variants_get_night_icons() {
+        assert_eq!(WeatherSymbol::ClearSky.icon(Some(Variant::Night)), "🌙");
+        assert_eq!(WeatherSymbol::ClearSky.icon(Some(Variant::Day)), "☀️");
+        assert_eq!(WeatherSymbol::RainShowers.icon(Some(Variant::Night)), "🌧️");
+    }
+}
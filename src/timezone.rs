@@ -0,0 +1,399 @@
+use jiff::civil::{DateTime, Time};
+use jiff::tz::{self, Offset};
+use jiff::{Span, Timestamp};
+
+pub use jiff::civil::Date;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Parses an RFC 3339 timestamp into Unix seconds: UTC such as
+/// `2024-05-01T12:00:00Z`, as locationforecast uses, or with an offset and
+/// without seconds such as `2024-05-01T14:00+02:00`, as the Sunrise API uses.
+/// Fractional seconds are dropped.
+pub fn parse_utc(time: &str) -> Option<i64> {
+    time.parse::<Timestamp>().ok().map(Timestamp::as_second)
+}
+
+/// Parses a point in time given by a user: `now`, an offset from now such
+/// as `+6h`, `+90m`, `+1d12h` or `-2h`, or an ISO 8601 date and time like
+/// `2024-05-04T14:00`, `2024-05-04 14:00+02:00` or `2024-05-04`.
+///
+/// Dates and times without a UTC offset are read as local time in `tz`.
+/// Offsets reaching beyond the years -9999 to 9999 are rejected.
+pub fn parse_time(input: &str, tz: &TimeZone, now: i64) -> Option<i64> {
+    let input = input.trim();
+    if input.eq_ignore_ascii_case("now") {
+        return Some(now);
+    }
+    if let Some(relative) = input.strip_prefix('+') {
+        return now.checked_add(parse_duration(relative)?).filter(|&time| is_representable(time));
+    }
+    if let Some(relative) = input.strip_prefix('-') {
+        return now.checked_sub(parse_duration(relative)?).filter(|&time| is_representable(time));
+    }
+
+    let (date, clock) = match input.split_once(['T', 't', ' ']) {
+        Some((date, clock)) => (date, Some(clock)),
+        None => (input, None),
+    };
+    let date: Date = date.parse().ok()?;
+    let Some(clock) = clock else {
+        return Some(tz.timestamp(date, 0));
+    };
+
+    // Split off a trailing `Z` or `±HH:MM`
+    let (clock, offset) = if let Some(clock) = clock.strip_suffix(['Z', 'z']) {
+        (clock, Some(0))
+    } else if let Some(index) = clock.rfind(['+', '-']) {
+        (&clock[..index], Some(parse_fixed_offset(&clock[index..])?))
+    } else {
+        (clock, None)
+    };
+
+    let mut clock_parts = clock.splitn(3, ':');
+    let hours: u32 = clock_parts.next()?.parse().ok()?;
+    let minutes: u32 = clock_parts.next().unwrap_or("0").parse().ok()?;
+    let seconds: u32 = clock_parts.next().unwrap_or("0").split('.').next()?.parse().ok()?;
+    if hours > 24 || minutes > 59 || seconds > 59 || (hours == 24 && minutes + seconds > 0) {
+        return None;
+    }
+    let seconds = hours * 3600 + minutes * 60 + seconds;
+
+    Some(match offset {
+        Some(offset) => TimeZone::utc().timestamp(date, seconds) - i64::from(offset),
+        None => tz.timestamp(date, seconds),
+    })
+}
+
+/// Whether Unix seconds fall within the range of calendar dates
+fn is_representable(timestamp: i64) -> bool {
+    (Timestamp::MIN.as_second()..=Timestamp::MAX.as_second()).contains(&timestamp)
+}
+
+/// Parses a duration made of `<number><unit>` parts with units `d`, `h`
+/// and `m`, e.g. `6h` or `1d12h`, into seconds
+fn parse_duration(input: &str) -> Option<i64> {
+    let mut rest = input.trim();
+    let mut total = 0i64;
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        let number: i64 = rest[..digits].parse().ok()?;
+        rest = rest[digits..].trim_start();
+        let unit_len = rest.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(rest.len());
+        let unit = match &rest[..unit_len] {
+            "d" | "day" | "days" => SECONDS_PER_DAY,
+            "h" | "hr" | "hrs" | "hour" | "hours" => 3600,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60,
+            _ => return None,
+        };
+        total = total.checked_add(number.checked_mul(unit)?)?;
+        rest = rest[unit_len..].trim_start();
+    }
+    (total > 0 || input.trim().starts_with('0')).then_some(total)
+}
+
+/// Formats Unix seconds as a UTC RFC 3339 timestamp
+pub fn format_utc(timestamp: i64) -> String {
+    let timestamp = timestamp.clamp(Timestamp::MIN.as_second(), Timestamp::MAX.as_second());
+    Timestamp::from_second(timestamp).unwrap_or(Timestamp::UNIX_EPOCH).to_string()
+}
+
+/// Formats a UTC offset in seconds as `+HH:MM`
+pub fn format_offset(offset: i32) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let offset = offset.unsigned_abs();
+    format!("{}{:02}:{:02}", sign, offset / 3600, offset % 3600 / 60)
+}
+
+/// Wall-clock time at some instant in a particular zone
+#[derive(Debug, Clone, Copy)]
+pub struct LocalTime {
+    pub date: Date,
+    /// Seconds since local midnight
+    pub seconds: u32,
+}
+
+impl LocalTime {
+    pub fn hour(self) -> u32 {
+        self.seconds / 3600
+    }
+}
+
+/// A time zone: either a fixed offset or an IANA zone from the tz database
+/// bundled into the binary
+#[derive(Debug, Clone)]
+pub struct TimeZone {
+    name: String,
+    /// Whether the zone was guessed from the longitude rather than given
+    estimated: bool,
+    rules: tz::TimeZone,
+}
+
+impl TimeZone {
+    /// Coordinated Universal Time
+    pub fn utc() -> Self {
+        Self::fixed("UTC".to_string(), 0)
+    }
+
+    fn fixed(name: String, offset: i32) -> Self {
+        Self {
+            name,
+            estimated: false,
+            // Callers keep offsets within ±14 hours, well inside jiff's range
+            rules: Offset::from_seconds(offset).map_or(tz::TimeZone::UTC, tz::TimeZone::fixed),
+        }
+    }
+
+    /// Approximates the zone of a place by its solar time, rounded to whole
+    /// hours. Ignores political boundaries and daylight saving time, so it
+    /// can be an hour or two off.
+    pub fn from_longitude(longitude: f64) -> Self {
+        let hours = (longitude / 15.0).round().clamp(-12.0, 14.0) as i32;
+        let offset = hours * 3600;
+        let mut zone = Self::fixed(format!("UTC{}", format_offset(offset)), offset);
+        zone.estimated = true;
+        zone
+    }
+
+    /// Looks up a zone by IANA name (e.g. `Europe/Oslo`), or parses a fixed
+    /// offset such as `UTC`, `+09:00` or `UTC-03:30`
+    pub fn from_name(name: &str) -> Option<Self> {
+        if name.eq_ignore_ascii_case("utc") || name.eq_ignore_ascii_case("z") {
+            return Some(Self::utc());
+        }
+        let offset_str = name
+            .strip_prefix("UTC")
+            .or_else(|| name.strip_prefix("GMT"))
+            .unwrap_or(name);
+        if offset_str.starts_with(['+', '-']) {
+            let offset = parse_fixed_offset(offset_str)?;
+            return Some(Self::fixed(format!("UTC{}", format_offset(offset)), offset));
+        }
+
+        let rules = tz::db().get(name).ok()?;
+        Some(Self {
+            // The database matches names case-insensitively; report its spelling
+            name: rules.iana_name().unwrap_or(name).to_string(),
+            estimated: false,
+            rules,
+        })
+    }
+
+    /// IANA name or `UTC±HH:MM`
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether the zone was derived from the longitude
+    pub fn is_estimated(&self) -> bool {
+        self.estimated
+    }
+
+    /// Offset from UTC in seconds at the given instant
+    pub fn offset_at(&self, timestamp: i64) -> i32 {
+        let timestamp = timestamp.clamp(Timestamp::MIN.as_second(), Timestamp::MAX.as_second());
+        Timestamp::from_second(timestamp).map_or(0, |t| self.rules.to_offset(t).seconds())
+    }
+
+    /// Instant at which the wall clock shows `seconds` past midnight on
+    /// `date`; a time skipped by a DST gap is read with the offset before
+    /// the gap, and a repeated one picks the earlier instant
+    pub fn timestamp(&self, date: Date, seconds: u32) -> i64 {
+        let wall = date
+            .to_datetime(Time::midnight())
+            .checked_add(Span::new().seconds(i64::from(seconds)))
+            .unwrap_or(DateTime::MAX);
+        self.rules
+            .to_ambiguous_timestamp(wall)
+            .compatible()
+            .map_or(Timestamp::MAX.as_second(), Timestamp::as_second)
+    }
+
+    /// Wall-clock time at the given instant
+    pub fn local(&self, timestamp: i64) -> LocalTime {
+        let timestamp = timestamp.clamp(Timestamp::MIN.as_second(), Timestamp::MAX.as_second());
+        let wall = self.rules.to_datetime(Timestamp::from_second(timestamp).unwrap_or(Timestamp::UNIX_EPOCH));
+        let time = wall.time();
+        LocalTime {
+            date: wall.date(),
+            seconds: time.hour() as u32 * 3600 + time.minute() as u32 * 60 + time.second() as u32,
+        }
+    }
+}
+
+/// Parses `±HH`, `±HHMM` or `±HH:MM`
+fn parse_fixed_offset(offset: &str) -> Option<i32> {
+    let (sign, rest) = if let Some(rest) = offset.strip_prefix('+') {
+        (1, rest)
+    } else {
+        (-1, offset.strip_prefix('-')?)
+    };
+    // Only digits and a colon, which also makes byte indexing below safe
+    if rest.is_empty() || !rest.bytes().all(|b| b.is_ascii_digit() || b == b':') {
+        return None;
+    }
+    let (hours, minutes) = match rest.split_once(':') {
+        Some((hours, minutes)) => (hours, minutes),
+        None if rest.len() > 2 => rest.split_at(2),
+        None => (rest, "0"),
+    };
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    if hours > 14 || minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_fixed_offsets() {
+        assert_eq!(parse_fixed_offset("+09:00"), Some(9 * 3600));
+        assert_eq!(parse_fixed_offset("-0330"), Some(-(3 * 3600 + 30 * 60)));
+        assert_eq!(parse_fixed_offset("+5"), Some(5 * 3600));
+        assert_eq!(parse_fixed_offset("+15:00"), None);
+        assert_eq!(parse_fixed_offset("+01:60"), None);
+        assert_eq!(parse_fixed_offset("09:00"), None);
+        assert_eq!(parse_fixed_offset("+"), None);
+        assert_eq!(parse_fixed_offset("++5"), None);
+    }
+
+    #[test]
+    fn rejects_non_ascii_offsets_without_panicking() {
+        assert_eq!(parse_fixed_offset("+1é"), None);
+        assert_eq!(parse_fixed_offset("-é1"), None);
+        assert!(TimeZone::from_name("+1é").is_none());
+        assert!(TimeZone::from_name("UTC+1é").is_none());
+        assert!(parse_utc("2024-05-04T14:00+1é").is_none());
+    }
+
+    #[test]
+    fn parse_time_reads_offsets() {
+        let tz = TimeZone::from_longitude(135.0);
+        let now = parse_utc("2024-05-04T00:00:00Z").unwrap();
+        let expected = parse_utc("2024-05-04T12:00:00Z");
+        assert_eq!(parse_time("2024-05-04T14:00+02:00", &tz, now), expected);
+        assert_eq!(parse_time("2024-05-04 09:00-0300", &tz, now), expected);
+        assert_eq!(parse_time("2024-05-04T12:00Z", &tz, now), expected);
+        // Without an offset the time is local to `tz`, here UTC+09:00
+        assert_eq!(parse_time("2024-05-04T21:00", &tz, now), expected);
+        assert_eq!(parse_time("+12h", &tz, now), expected);
+    }
+
+    #[test]
+    fn parse_time_rejects_overflowing_relative_times() {
+        let now = utc("2024-05-04T12:00:00Z");
+        assert_eq!(parse_time("+106751991167300d", &TimeZone::utc(), now), None);
+        assert_eq!(parse_time("-106751991167300d", &TimeZone::utc(), now), None);
+        assert_eq!(parse_time("+9223372036854775807m", &TimeZone::utc(), now), None);
+        assert_eq!(parse_time("-2h", &TimeZone::utc(), now), Some(now - 2 * 3600));
+    }
+
+    #[test]
+    fn parse_time_rejects_bad_offsets_without_panicking() {
+        let tz = TimeZone::utc();
+        assert_eq!(parse_time("2024-05-04T14:00+1é", &tz, 0), None);
+        assert_eq!(parse_time("2024-05-04T14:00+é", &tz, 0), None);
+        assert_eq!(parse_time("2024-05-04T14:00+25:00", &tz, 0), None);
+    }
+
+    fn utc(time: &str) -> i64 {
+        parse_utc(time).unwrap()
+    }
+
+    #[test]
+    fn parses_rfc3339_timestamps() {
+        assert_eq!(parse_utc("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_utc("2024-02-29T12:00:00Z"), Some(1_709_208_000));
+        assert_eq!(parse_utc("2024-02-29T12:00:00.75Z"), Some(1_709_208_000));
+        assert_eq!(parse_utc("2024-02-29T13:00:00+01:00"), Some(1_709_208_000));
+        assert_eq!(parse_utc("2023-02-29T12:00:00Z"), None);
+        assert_eq!(parse_utc("2024-02-29T12:00:00"), None);
+        assert_eq!(parse_utc("2024-02-29"), None);
+    }
+
+    #[test]
+    fn fixed_offset_zones() {
+        let tokyo = TimeZone::from_name("+09:00").unwrap();
+        assert_eq!(tokyo.name(), "UTC+09:00");
+        assert_eq!(tokyo.offset_at(utc("2024-01-01T00:00:00Z")), 9 * 3600);
+        assert_eq!(TimeZone::from_name("UTC-03:30").unwrap().offset_at(0), -(3 * 3600 + 30 * 60));
+        assert_eq!(TimeZone::from_name("GMT+2").unwrap().name(), "UTC+02:00");
+        assert_eq!(TimeZone::from_name("utc").unwrap().offset_at(0), 0);
+
+        let estimated = TimeZone::from_longitude(-74.0);
+        assert_eq!(estimated.name(), "UTC-05:00");
+        assert!(estimated.is_estimated());
+        assert_eq!(TimeZone::from_longitude(179.9).offset_at(0), 12 * 3600);
+    }
+
+    #[test]
+    fn looks_up_iana_zones_in_the_bundled_database() {
+        assert_eq!(TimeZone::from_name("Europe/Oslo").unwrap().name(), "Europe/Oslo");
+        assert_eq!(TimeZone::from_name("asia/seoul").unwrap().name(), "Asia/Seoul");
+        assert!(TimeZone::from_name("Europe/Atlantis").is_none());
+        assert!(TimeZone::from_name("../../etc/passwd").is_none());
+        assert!(TimeZone::from_name("").is_none());
+    }
+
+    #[test]
+    fn follows_daylight_saving_transitions() {
+        let oslo = TimeZone::from_name("Europe/Oslo").unwrap();
+        // Summer time began 2024-03-31 at 01:00 UTC and ended 2024-10-27 at 01:00 UTC
+        assert_eq!(oslo.offset_at(utc("2024-03-31T00:59:59Z")), 3600);
+        assert_eq!(oslo.offset_at(utc("2024-03-31T01:00:00Z")), 7200);
+        assert_eq!(oslo.offset_at(utc("2024-10-27T00:59:59Z")), 7200);
+        assert_eq!(oslo.offset_at(utc("2024-10-27T01:00:00Z")), 3600);
+
+        // Southern hemisphere: daylight saving spans the new year
+        let sydney = TimeZone::from_name("Australia/Sydney").unwrap();
+        assert_eq!(sydney.offset_at(utc("2024-01-15T00:00:00Z")), 11 * 3600);
+        assert_eq!(sydney.offset_at(utc("2024-07-15T00:00:00Z")), 10 * 3600);
+
+        let new_york = TimeZone::from_name("America/New_York").unwrap();
+        assert_eq!(new_york.offset_at(utc("2024-03-10T06:59:59Z")), -5 * 3600);
+        assert_eq!(new_york.offset_at(utc("2024-03-10T07:00:00Z")), -4 * 3600);
+    }
+
+    #[test]
+    fn applies_the_recurring_rule_past_the_last_listed_transition() {
+        // Far-future dates come from the zone's POSIX rule, e.g.
+        // CET-1CEST,M3.5.0,M10.5.0/3 for Oslo: last Sunday of March and October
+        let oslo = TimeZone::from_name("Europe/Oslo").unwrap();
+        assert_eq!(oslo.offset_at(utc("2100-03-28T00:59:59Z")), 3600);
+        assert_eq!(oslo.offset_at(utc("2100-03-28T01:00:00Z")), 7200);
+        assert_eq!(oslo.offset_at(utc("2100-10-31T01:00:00Z")), 3600);
+        let sydney = TimeZone::from_name("Australia/Sydney").unwrap();
+        assert_eq!(sydney.offset_at(utc("2100-01-01T00:00:00Z")), 11 * 3600);
+        assert_eq!(sydney.offset_at(utc("2100-06-01T00:00:00Z")), 10 * 3600);
+    }
+
+    #[test]
+    fn converts_between_wall_clock_and_instants() {
+        let oslo = TimeZone::from_name("Europe/Oslo").unwrap();
+        let date = "2024-07-01".parse::<Date>().unwrap();
+        assert_eq!(oslo.timestamp(date, 12 * 3600), utc("2024-07-01T10:00:00Z"));
+        let local = oslo.local(utc("2024-07-01T22:30:00Z"));
+        assert_eq!(local.date, jiff::civil::date(2024, 7, 2));
+        assert_eq!((local.hour(), local.seconds % 3600 / 60), (0, 30));
+
+        // Midnight on the days of both transitions
+        let spring = "2024-03-31".parse::<Date>().unwrap();
+        assert_eq!(oslo.timestamp(spring, 0), utc("2024-03-30T23:00:00Z"));
+        let autumn = "2024-10-27".parse::<Date>().unwrap();
+        assert_eq!(oslo.timestamp(autumn, 0), utc("2024-10-26T22:00:00Z"));
+        assert_eq!(oslo.timestamp(jiff::civil::date(2024, 10, 28), 0) - oslo.timestamp(autumn, 0), 25 * 3600);
+    }
+
+    #[test]
+    fn formats_utc_and_offsets() {
+        assert_eq!(format_utc(utc("2024-05-04T14:05:09Z")), "2024-05-04T14:05:09Z");
+        assert_eq!(format_utc(-1), "1969-12-31T23:59:59Z");
+        assert_eq!(format_offset(-(9 * 3600 + 30 * 60)), "-09:30");
+        assert_eq!(format_offset(0), "+00:00");
+        assert_eq!(parse_utc("2024-05-01T14:00+02:00"), Some(utc("2024-05-01T12:00:00Z")));
+    }
+}
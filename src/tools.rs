@@ -0,0 +1,1164 @@
+use jiff::ToSpan;
+use serde_json::{json, Map, Value};
+use std::ops::RangeInclusive;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{error, info};
+
+use crate::forecast::{Forecast, ForecastType, Units};
+use crate::i18n::Language;
+use crate::nowcast::Nowcast;
+use crate::session::{Notifier, Progress, Session};
+use crate::sun_moon::SunMoon;
+use crate::timezone::{self, Date, TimeZone};
+use crate::units::{PrecipitationUnit, PressureUnit, SpeedUnit, TemperatureUnit, UnitPreset};
+use crate::weather::{self, ForecastRequest, NowcastRequest, SunMoonRequest, WeatherClient};
+use crate::{supports_structured_output, JsonRpcError, ToolCall};
+
+/// Altitudes accepted by the forecast tools, in meters; from the Dead Sea
+/// shore to above Everest
+const ALTITUDE_RANGE: RangeInclusive<i64> = -500..=9000;
+/// Window `get_hourly_forecast` covers when no end is given
+const DEFAULT_HOURLY_WINDOW: i64 = 12 * 3600;
+/// How far from now `get_hourly_forecast` accepts times, in days;
+/// locationforecast reaches about 10 days ahead
+const FORECAST_HORIZON_DAYS: i64 = 10;
+/// Number of days `get_sun_moon` describes at once
+const SUN_MOON_DAYS: RangeInclusive<i64> = 1..=7;
+/// How far from today the first day of `get_sun_moon` may be, in days
+const SUN_MOON_DATE_RANGE: i32 = 366;
+
+/// Tool catalogue returned by `tools/list`
+pub fn list_tools(session: &Session) -> Value {
+    let mut get_weather_properties = shared_properties();
+    get_weather_properties["forecast_type"] = json!({
+        "type": "string",
+        "enum": ["current", "tomorrow", "weekly"],
+        "description": "Type of forecast: 'current' for now, 'tomorrow' for next day, 'weekly' for 7-day forecast",
+        "default": "current"
+    });
+    get_weather_properties["include_sun_moon"] = json!({
+        "type": "boolean",
+        "description": "Add sunrise, sunset, day length and moon phase to each day of the 'tomorrow' and 'weekly' views",
+        "default": false
+    });
+    let mut get_weather = json!({
+        "name": "get_weather",
+        "description": "Get weather forecast for GPS coordinates using YR.no API",
+        "inputSchema": {
+            "type": "object",
+            "properties": get_weather_properties,
+            "required": ["latitude", "longitude"]
+        }
+    });
+
+    let mut hourly_properties = shared_properties();
+    hourly_properties["start"] = json!({
+        "type": "string",
+        "description": "Start of the window: 'now', an offset from now like '+6h' or '+1d', or an ISO 8601 time like '2024-05-04T14:00' (local time unless it has an offset); at most 10 days from now",
+        "default": "now"
+    });
+    hourly_properties["end"] = json!({
+        "type": "string",
+        "description": "End of the window, in the same formats as start; defaults to 12 hours after start"
+    });
+    let mut get_hourly_forecast = json!({
+        "name": "get_hourly_forecast",
+        "description": "Get every forecast step between two times for GPS coordinates using YR.no API. Steps are hourly for the next ~2.5 days and 6-hourly after that",
+        "inputSchema": {
+            "type": "object",
+            "properties": hourly_properties,
+            "required": ["latitude", "longitude"]
+        }
+    });
+
+    let shared = shared_properties();
+    let mut sun_moon_properties = json!({});
+    for name in ["latitude", "longitude", "location_name", "timezone", "language"] {
+        sun_moon_properties[name] = shared[name].clone();
+    }
+    sun_moon_properties["date"] = json!({
+        "type": "string",
+        "description": "First local day: 'today', 'tomorrow', a date like '2024-05-04' or an offset like '+3d'; at most a year from today",
+        "default": "today"
+    });
+    sun_moon_properties["days"] = json!({
+        "type": "integer",
+        "minimum": SUN_MOON_DAYS.start(),
+        "maximum": SUN_MOON_DAYS.end(),
+        "description": "Number of consecutive days",
+        "default": 1
+    });
+    let mut get_sun_moon = json!({
+        "name": "get_sun_moon",
+        "description": "Get sunrise, sunset, solar noon, day length, moonrise, moonset and moon phase for GPS coordinates using met.no's Sunrise API, calculated offline when it cannot be reached",
+        "inputSchema": {
+            "type": "object",
+            "properties": sun_moon_properties,
+            "required": ["latitude", "longitude"]
+        }
+    });
+
+    let mut nowcast_properties = json!({});
+    for name in [
+        "latitude",
+        "longitude",
+        "location_name",
+        "timezone",
+        "language",
+        "units",
+        "temperature_unit",
+        "wind_speed_unit",
+        "precipitation_unit",
+    ] {
+        nowcast_properties[name] = shared[name].clone();
+    }
+    let mut get_nowcast = json!({
+        "name": "get_nowcast",
+        "description": "Get radar-based precipitation for the next two hours in five-minute steps, with when rain starts or stops, using met.no's nowcast. Only covers the Nordic countries; use get_hourly_forecast elsewhere",
+        "inputSchema": {
+            "type": "object",
+            "properties": nowcast_properties,
+            "required": ["latitude", "longitude"]
+        }
+    });
+
+    if supports_structured_output(session) {
+        get_weather["outputSchema"] = Forecast::output_schema();
+        get_hourly_forecast["outputSchema"] = Forecast::output_schema();
+        get_sun_moon["outputSchema"] = SunMoon::output_schema();
+        get_nowcast["outputSchema"] = Nowcast::output_schema();
+    }
+
+    json!({
+        "tools": [get_weather, get_hourly_forecast, get_sun_moon, get_nowcast]
+    })
+}
+
+/// Names of the [`shared_properties`]
+const SHARED_PARAMETERS: [&str; 12] = [
+    "latitude",
+    "longitude",
+    "altitude",
+    "location_name",
+    "timezone",
+    "detailed",
+    "language",
+    "units",
+    "temperature_unit",
+    "wind_speed_unit",
+    "precipitation_unit",
+    "pressure_unit",
+];
+
+/// Input schema properties shared by the forecast tools
+fn shared_properties() -> Value {
+    json!({
+        "latitude": {
+            "type": "number",
+            "minimum": -90,
+            "maximum": 90,
+            "description": "Latitude coordinate (e.g., 37.5665 for Seoul)"
+        },
+        "longitude": {
+            "type": "number",
+            "minimum": -180,
+            "maximum": 180,
+            "description": "Longitude coordinate (e.g., 126.9780 for Seoul)"
+        },
+        "altitude": {
+            "type": "integer",
+            "minimum": ALTITUDE_RANGE.start(),
+            "maximum": ALTITUDE_RANGE.end(),
+            "description": "Optional ground height in meters above sea level; set it for mountain sites, where met.no's terrain model can be far off"
+        },
+        "location_name": {
+            "type": "string",
+            "description": "Optional location name for display purposes"
+        },
+        "timezone": {
+            "type": "string",
+            "description": "IANA time zone of the location (e.g. 'Europe/Oslo') or a UTC offset like '+09:00', used for local days and times. Estimated from the longitude when omitted"
+        },
+        "detailed": {
+            "type": "boolean",
+            "description": "Include dew point, fog, cloud layers, UV index, wind gusts, min/max temperature and chance of precipitation",
+            "default": false
+        },
+        "language": {
+            "type": "string",
+            "enum": ["en", "ko", "nb"],
+            "description": "Language of the forecast text: English, Korean or Norwegian Bokmål. Defaults to the server's configured language"
+        },
+        "units": {
+            "type": "string",
+            "enum": ["metric", "imperial", "uk"],
+            "description": "Unit system: 'metric' (°C, m/s, mm, hPa), 'imperial' (°F, mph, in, inHg) or 'uk' (°C, mph, mm, hPa). The *_unit arguments override single quantities for custom mixes",
+            "default": "metric"
+        },
+        "temperature_unit": {
+            "type": "string",
+            "enum": ["celsius", "fahrenheit"]
+        },
+        "wind_speed_unit": {
+            "type": "string",
+            "enum": ["m/s", "km/h", "mph", "knots", "beaufort"]
+        },
+        "precipitation_unit": {
+            "type": "string",
+            "enum": ["mm", "in"]
+        },
+        "pressure_unit": {
+            "type": "string",
+            "enum": ["hPa", "inHg", "mmHg"]
+        }
+    })
+}
+
+/// Runs a `tools/call` request.
+///
+/// Problems with the call itself (unknown tool, malformed params) are
+/// JSON-RPC errors; anything the model can fix or react to, including bad
+/// arguments, comes back as a result with `isError` set.
+pub async fn call_tool(
+    params: Value,
+    weather_client: &WeatherClient,
+    session: &Session,
+    notifier: &Notifier,
+) -> Result<Value, JsonRpcError> {
+    let call: ToolCall = serde_json::from_value(params).map_err(|e| JsonRpcError {
+        code: -32602,
+        message: format!("Invalid parameters: {}", e),
+        data: None,
+    })?;
+
+    let arguments = call.arguments.unwrap_or_else(|| json!({}));
+    let progress_token = call.meta.as_ref().and_then(|m| m.get("progressToken")).cloned();
+    let progress = Progress::new(progress_token, notifier.clone());
+
+    match call.name.as_str() {
+        "get_weather" => Ok(get_weather(&arguments, weather_client, session, &progress).await),
+        "get_hourly_forecast" => Ok(get_hourly_forecast(&arguments, weather_client, session, &progress).await),
+        "get_sun_moon" => Ok(get_sun_moon(&arguments, weather_client, session, &progress).await),
+        "get_nowcast" => Ok(get_nowcast(&arguments, weather_client, session, &progress).await),
+        name => Err(JsonRpcError {
+            code: -32601,
+            message: format!("Unknown tool: {}", name),
+            data: None,
+        }),
+    }
+}
+
+/// Validated arguments shared by the forecast tools
+struct SharedArgs {
+    latitude: f64,
+    longitude: f64,
+    altitude: Option<i32>,
+    location_name: String,
+    detailed: bool,
+    timezone: TimeZone,
+    language: Language,
+    units: Units,
+}
+
+impl SharedArgs {
+    /// Reads the shared arguments, recording every offending field in
+    /// `problems`
+    fn parse(arguments: &Map<String, Value>, default_language: Language, problems: &mut Vec<String>) -> Option<Self> {
+        let latitude = number_arg(arguments, "latitude", -90.0..=90.0, problems);
+        let longitude = number_arg(arguments, "longitude", -180.0..=180.0, problems);
+        let altitude = optional_integer_arg(arguments, "altitude", ALTITUDE_RANGE, problems);
+        let location_name = optional_string_arg(arguments, "location_name", problems);
+        let detailed = optional_bool_arg(arguments, "detailed", problems);
+        let timezone = timezone_arg(arguments, longitude, problems);
+        let language = optional_choice_arg(arguments, "language", &Language::ALL, Language::as_str, problems)
+            .unwrap_or(default_language);
+        let units = units_arg(arguments, problems);
+
+        Some(Self {
+            latitude: latitude?,
+            longitude: longitude?,
+            altitude: altitude.and_then(|a| i32::try_from(a).ok()),
+            location_name: location_name.unwrap_or_else(|| language.messages().unknown_location.to_string()),
+            detailed: detailed.unwrap_or(false),
+            timezone: timezone?,
+            language,
+            units,
+        })
+    }
+
+    /// The met.no lookup for these arguments
+    fn request(&self, forecast_type: ForecastType, window: Option<(i64, i64)>) -> ForecastRequest<'_> {
+        ForecastRequest {
+            latitude: self.latitude,
+            longitude: self.longitude,
+            altitude: self.altitude,
+            location_name: &self.location_name,
+            forecast_type,
+            detailed: self.detailed,
+            timezone: &self.timezone,
+            window,
+            language: self.language,
+            units: self.units,
+            sun_moon: false,
+        }
+    }
+}
+
+/// Validated arguments of `get_weather`
+struct GetWeatherArgs {
+    shared: SharedArgs,
+    forecast_type: ForecastType,
+    include_sun_moon: bool,
+}
+
+impl GetWeatherArgs {
+    /// Checks `arguments` against the declared input schema, listing every
+    /// offending field on failure
+    fn parse(arguments: &Value, default_language: Language) -> Result<Self, Vec<String>> {
+        let Some(arguments) = arguments.as_object() else {
+            return Err(vec!["arguments: must be an object".to_string()]);
+        };
+
+        let mut problems = Vec::new();
+        let shared = SharedArgs::parse(arguments, default_language, &mut problems);
+        let forecast_type =
+            optional_choice_arg(arguments, "forecast_type", &ForecastType::ALL, ForecastType::as_str, &mut problems)
+                .unwrap_or(ForecastType::Current);
+        let include_sun_moon = optional_bool_arg(arguments, "include_sun_moon", &mut problems).unwrap_or(false);
+        let known = [SHARED_PARAMETERS.as_slice(), &["forecast_type", "include_sun_moon"]].concat();
+        unknown_args(arguments, &known, "get_weather", &mut problems);
+
+        match shared {
+            Some(shared) if problems.is_empty() => Ok(Self {
+                shared,
+                forecast_type,
+                include_sun_moon,
+            }),
+            _ => Err(problems),
+        }
+    }
+}
+
+/// Validated arguments of `get_hourly_forecast`
+struct HourlyArgs {
+    shared: SharedArgs,
+    /// Window start in Unix seconds
+    start: i64,
+    /// Window end in Unix seconds
+    end: i64,
+}
+
+impl HourlyArgs {
+    /// Checks `arguments` against the declared input schema, listing every
+    /// offending field on failure
+    fn parse(arguments: &Value, default_language: Language, now: i64) -> Result<Self, Vec<String>> {
+        let Some(arguments) = arguments.as_object() else {
+            return Err(vec!["arguments: must be an object".to_string()]);
+        };
+
+        let mut problems = Vec::new();
+        let shared = SharedArgs::parse(arguments, default_language, &mut problems);
+        let start = optional_string_arg(arguments, "start", &mut problems);
+        let end = optional_string_arg(arguments, "end", &mut problems);
+        let known = [SHARED_PARAMETERS.as_slice(), &["start", "end"]].concat();
+        unknown_args(arguments, &known, "get_hourly_forecast", &mut problems);
+
+        // Times can only be read once the time zone is known
+        let Some(shared) = shared.filter(|_| problems.is_empty()) else {
+            return Err(problems);
+        };
+        let start = match start {
+            None => Some(now),
+            Some(start) => time_arg("start", &start, &shared.timezone, now, &mut problems),
+        };
+        let end = match (end, start) {
+            (None, Some(start)) => {
+                let end = start.checked_add(DEFAULT_HOURLY_WINDOW);
+                if end.is_none() {
+                    problems.push("start: too late to end the window 12 hours after it".to_string());
+                }
+                end
+            }
+            (None, None) => None,
+            (Some(end), _) => time_arg("end", &end, &shared.timezone, now, &mut problems),
+        };
+
+        match (start, end) {
+            (Some(start), Some(end)) if end <= start => {
+                problems.push("end: must be after start".to_string());
+                Err(problems)
+            }
+            (Some(start), Some(end)) if problems.is_empty() => Ok(Self { shared, start, end }),
+            _ => Err(problems),
+        }
+    }
+}
+
+/// Validated arguments of `get_sun_moon`
+struct SunMoonArgs {
+    latitude: f64,
+    longitude: f64,
+    location_name: String,
+    timezone: TimeZone,
+    language: Language,
+    start: Date,
+    days: u32,
+}
+
+impl SunMoonArgs {
+    /// Checks `arguments` against the declared input schema, listing every
+    /// offending field on failure
+    fn parse(arguments: &Value, default_language: Language, now: i64) -> Result<Self, Vec<String>> {
+        let Some(arguments) = arguments.as_object() else {
+            return Err(vec!["arguments: must be an object".to_string()]);
+        };
+
+        let mut problems = Vec::new();
+        let latitude = number_arg(arguments, "latitude", -90.0..=90.0, &mut problems);
+        let longitude = number_arg(arguments, "longitude", -180.0..=180.0, &mut problems);
+        let location_name = optional_string_arg(arguments, "location_name", &mut problems);
+        let timezone = timezone_arg(arguments, longitude, &mut problems);
+        let language = optional_choice_arg(arguments, "language", &Language::ALL, Language::as_str, &mut problems)
+            .unwrap_or(default_language);
+        let date = optional_string_arg(arguments, "date", &mut problems);
+        let days = optional_integer_arg(arguments, "days", SUN_MOON_DAYS, &mut problems).map_or(Ok(1), u32::try_from);
+        unknown_args(
+            arguments,
+            &["latitude", "longitude", "location_name", "timezone", "language", "date", "days"],
+            "get_sun_moon",
+            &mut problems,
+        );
+
+        // The date can only be read once the time zone is known
+        let (Some(latitude), Some(longitude), Some(timezone)) = (latitude, longitude, timezone) else {
+            return Err(problems);
+        };
+        let start = match date {
+            None => Some(timezone.local(now).date),
+            Some(date) => date_arg("date", &date, &timezone, now, &mut problems),
+        };
+
+        match (start, days) {
+            (Some(start), Ok(days)) if problems.is_empty() => Ok(Self {
+                latitude,
+                longitude,
+                location_name: location_name.unwrap_or_else(|| language.messages().unknown_location.to_string()),
+                timezone,
+                language,
+                start,
+                days,
+            }),
+            _ => Err(problems),
+        }
+    }
+}
+
+/// Validated arguments of `get_nowcast`
+struct NowcastArgs {
+    latitude: f64,
+    longitude: f64,
+    location_name: String,
+    timezone: TimeZone,
+    language: Language,
+    units: Units,
+}
+
+impl NowcastArgs {
+    /// Checks `arguments` against the declared input schema, listing every
+    /// offending field on failure
+    fn parse(arguments: &Value, default_language: Language) -> Result<Self, Vec<String>> {
+        let Some(arguments) = arguments.as_object() else {
+            return Err(vec!["arguments: must be an object".to_string()]);
+        };
+
+        let mut problems = Vec::new();
+        let latitude = number_arg(arguments, "latitude", -90.0..=90.0, &mut problems);
+        let longitude = number_arg(arguments, "longitude", -180.0..=180.0, &mut problems);
+        let location_name = optional_string_arg(arguments, "location_name", &mut problems);
+        let timezone = timezone_arg(arguments, longitude, &mut problems);
+        let language = optional_choice_arg(arguments, "language", &Language::ALL, Language::as_str, &mut problems)
+            .unwrap_or(default_language);
+        let units = units_arg(arguments, &mut problems);
+        unknown_args(
+            arguments,
+            &[
+                "latitude",
+                "longitude",
+                "location_name",
+                "timezone",
+                "language",
+                "units",
+                "temperature_unit",
+                "wind_speed_unit",
+                "precipitation_unit",
+            ],
+            "get_nowcast",
+            &mut problems,
+        );
+
+        match (latitude, longitude, timezone) {
+            (Some(latitude), Some(longitude), Some(timezone)) if problems.is_empty() => Ok(Self {
+                latitude,
+                longitude,
+                location_name: location_name.unwrap_or_else(|| language.messages().unknown_location.to_string()),
+                timezone,
+                language,
+                units,
+            }),
+            _ => Err(problems),
+        }
+    }
+}
+
+/// Handles the `get_weather` tool
+async fn get_weather(arguments: &Value, weather_client: &WeatherClient, session: &Session, progress: &Progress) -> Value {
+    let args = match GetWeatherArgs::parse(arguments, weather_client.default_language()) {
+        Ok(args) => args,
+        Err(problems) => return invalid_arguments("get_weather", &problems),
+    };
+
+    info!(
+        "get_weather ({}) for {} requested by {}",
+        args.forecast_type.as_str(),
+        args.shared.location_name,
+        client_name(session)
+    );
+
+    let mut request = args.shared.request(args.forecast_type, None);
+    request.sun_moon = args.include_sun_moon;
+    forecast_result(weather_client.get_weather_by_coords(&request, progress).await, session)
+}
+
+/// Handles the `get_hourly_forecast` tool
+async fn get_hourly_forecast(
+    arguments: &Value,
+    weather_client: &WeatherClient,
+    session: &Session,
+    progress: &Progress,
+) -> Value {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    let args = match HourlyArgs::parse(arguments, weather_client.default_language(), now) {
+        Ok(args) => args,
+        Err(problems) => return invalid_arguments("get_hourly_forecast", &problems),
+    };
+
+    info!(
+        "get_hourly_forecast ({} to {}) for {} requested by {}",
+        timezone::format_utc(args.start),
+        timezone::format_utc(args.end),
+        args.shared.location_name,
+        client_name(session)
+    );
+
+    let request = args.shared.request(ForecastType::Hourly, Some((args.start, args.end)));
+    forecast_result(weather_client.get_weather_by_coords(&request, progress).await, session)
+}
+
+/// Handles the `get_sun_moon` tool
+async fn get_sun_moon(arguments: &Value, weather_client: &WeatherClient, session: &Session, progress: &Progress) -> Value {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    let args = match SunMoonArgs::parse(arguments, weather_client.default_language(), now) {
+        Ok(args) => args,
+        Err(problems) => return invalid_arguments("get_sun_moon", &problems),
+    };
+
+    info!(
+        "get_sun_moon ({}, {} days) for {} requested by {}",
+        args.start,
+        args.days,
+        args.location_name,
+        client_name(session)
+    );
+
+    let request = SunMoonRequest {
+        latitude: args.latitude,
+        longitude: args.longitude,
+        location_name: &args.location_name,
+        timezone: &args.timezone,
+        start: args.start,
+        days: args.days,
+        today: args.timezone.local(now).date,
+        language: args.language,
+    };
+    let sun_moon = weather_client.get_sun_moon(&request, progress).await;
+
+    let mut result = text_result(sun_moon.to_markdown());
+    if supports_structured_output(session) {
+        result["structuredContent"] = json!(sun_moon);
+    }
+    result
+}
+
+/// Handles the `get_nowcast` tool
+async fn get_nowcast(arguments: &Value, weather_client: &WeatherClient, session: &Session, progress: &Progress) -> Value {
+    let args = match NowcastArgs::parse(arguments, weather_client.default_language()) {
+        Ok(args) => args,
+        Err(problems) => return invalid_arguments("get_nowcast", &problems),
+    };
+
+    info!("get_nowcast for {} requested by {}", args.location_name, client_name(session));
+
+    let request = NowcastRequest {
+        latitude: args.latitude,
+        longitude: args.longitude,
+        location_name: &args.location_name,
+        timezone: &args.timezone,
+        language: args.language,
+        units: args.units,
+    };
+    match weather_client.get_nowcast(&request, progress).await {
+        Ok(nowcast) => {
+            let mut result = text_result(nowcast.to_markdown());
+            if supports_structured_output(session) {
+                result["structuredContent"] = json!(nowcast);
+            }
+            result
+        }
+        Err(e) => {
+            error!("Nowcast lookup failed: {}", e);
+            error_result(format!("Failed to get nowcast: {}", e))
+        }
+    }
+}
+
+/// Client name and version for log messages
+fn client_name(session: &Session) -> String {
+    session
+        .client_info()
+        .map_or_else(|| "unknown client".to_string(), |c| format!("{} {}", c.name, c.version))
+}
+
+/// Tool result for a forecast lookup: Markdown text, plus structured
+/// content when the client supports it
+fn forecast_result(forecast: weather::Result<Forecast>, session: &Session) -> Value {
+    match forecast {
+        Ok(forecast) => {
+            let mut result = text_result(forecast.to_markdown());
+            if supports_structured_output(session) {
+                result["structuredContent"] = json!(forecast);
+            }
+            result
+        }
+        Err(e) => {
+            error!("Forecast lookup failed: {}", e);
+            error_result(format!("Failed to get weather: {}", e))
+        }
+    }
+}
+
+/// Reads the `units` preset and the per-quantity overrides on top of it
+fn units_arg(arguments: &Map<String, Value>, problems: &mut Vec<String>) -> Units {
+    let preset = optional_choice_arg(arguments, "units", &UnitPreset::ALL, UnitPreset::as_str, problems)
+        .unwrap_or(UnitPreset::Metric);
+    let mut units = Units::preset(preset);
+    if let Some(unit) = optional_choice_arg(
+        arguments,
+        "temperature_unit",
+        &TemperatureUnit::ALL,
+        TemperatureUnit::as_str,
+        problems,
+    ) {
+        units.temperature = unit;
+    }
+    if let Some(unit) = optional_choice_arg(arguments, "wind_speed_unit", &SpeedUnit::ALL, SpeedUnit::as_str, problems) {
+        units.wind_speed = unit;
+    }
+    if let Some(unit) = optional_choice_arg(
+        arguments,
+        "precipitation_unit",
+        &PrecipitationUnit::ALL,
+        PrecipitationUnit::as_str,
+        problems,
+    ) {
+        units.precipitation = unit;
+    }
+    if let Some(unit) = optional_choice_arg(arguments, "pressure_unit", &PressureUnit::ALL, PressureUnit::as_str, problems)
+    {
+        units.pressure = unit;
+    }
+    units
+}
+
+/// Parses a time argument within the forecast horizon, see
+/// [`timezone::parse_time`]
+fn time_arg(name: &str, value: &str, tz: &TimeZone, now: i64, problems: &mut Vec<String>) -> Option<i64> {
+    match timezone::parse_time(value, tz, now) {
+        Some(time) if time.abs_diff(now) <= (FORECAST_HORIZON_DAYS * 86_400).unsigned_abs() => Some(time),
+        Some(_) => {
+            problems.push(format!("{}: must be within {} days of now, got {:?}", name, FORECAST_HORIZON_DAYS, value));
+            None
+        }
+        None => {
+            problems.push(format!(
+                "{}: expected 'now', an offset like '+6h' or an ISO 8601 time like '2024-05-04T14:00', got {:?}",
+                name, value
+            ));
+            None
+        }
+    }
+}
+
+/// Parses a local date argument within about a year of today: `today`,
+/// `tomorrow`, an ISO 8601 date, or anything [`timezone::parse_time`]
+/// accepts
+fn date_arg(name: &str, value: &str, tz: &TimeZone, now: i64, problems: &mut Vec<String>) -> Option<Date> {
+    let today = tz.local(now).date;
+    let date = match value.trim() {
+        value if value.eq_ignore_ascii_case("today") => Some(today),
+        value if value.eq_ignore_ascii_case("tomorrow") => Some(today.saturating_add(1.day())),
+        value => timezone::parse_time(value, tz, now).map(|time| tz.local(time).date),
+    };
+    match date {
+        Some(date) if (date - today).get_days().abs() <= SUN_MOON_DATE_RANGE => Some(date),
+        Some(_) => {
+            problems.push(format!("{}: must be within a year of today, got {:?}", name, value));
+            None
+        }
+        None => {
+            problems.push(format!(
+                "{}: expected 'today', 'tomorrow', a date like '2024-05-04' or an offset like '+3d', got {:?}",
+                name, value
+            ));
+            None
+        }
+    }
+}
+
+/// Reads the optional `timezone` argument, falling back to a zone
+/// estimated from the longitude
+fn timezone_arg(
+    arguments: &Map<String, Value>,
+    longitude: Option<f64>,
+    problems: &mut Vec<String>,
+) -> Option<TimeZone> {
+    match optional_string_arg(arguments, "timezone", problems) {
+        None => longitude.map(TimeZone::from_longitude),
+        Some(name) => {
+            let timezone = TimeZone::from_name(&name);
+            if timezone.is_none() {
+                problems.push(format!(
+                    "timezone: unknown time zone {:?}; use an IANA name such as Europe/Oslo or an offset like +02:00",
+                    name
+                ));
+            }
+            timezone
+        }
+    }
+}
+
+/// Reads a required number argument and checks it lies within `range`
+fn number_arg(
+    arguments: &Map<String, Value>,
+    name: &str,
+    range: RangeInclusive<f64>,
+    problems: &mut Vec<String>,
+) -> Option<f64> {
+    let Some(value) = arguments.get(name) else {
+        problems.push(format!("{}: is required", name));
+        return None;
+    };
+    let Some(number) = value.as_f64() else {
+        problems.push(format!("{}: must be a number, got {}", name, value));
+        return None;
+    };
+    if !range.contains(&number) {
+        problems.push(format!(
+            "{}: must be between {} and {}, got {}",
+            name,
+            range.start(),
+            range.end(),
+            number
+        ));
+        return None;
+    }
+    Some(number)
+}
+
+/// Reads an optional integer argument and checks it lies within `range`
+fn optional_integer_arg(
+    arguments: &Map<String, Value>,
+    name: &str,
+    range: RangeInclusive<i64>,
+    problems: &mut Vec<String>,
+) -> Option<i64> {
+    let value = arguments.get(name).filter(|v| !v.is_null())?;
+    // Accept 1200.0 as well as 1200, but not 1200.5
+    let integer = value
+        .as_i64()
+        .or_else(|| value.as_f64().filter(|f| f.fract() == 0.0 && f.abs() < 1e15).map(|f| f as i64));
+    let Some(integer) = integer else {
+        problems.push(format!("{}: must be an integer, got {}", name, value));
+        return None;
+    };
+    if !range.contains(&integer) {
+        problems.push(format!(
+            "{}: must be between {} and {}, got {}",
+            name,
+            range.start(),
+            range.end(),
+            integer
+        ));
+        return None;
+    }
+    Some(integer)
+}
+
+/// Reads an optional string argument
+fn optional_string_arg(arguments: &Map<String, Value>, name: &str, problems: &mut Vec<String>) -> Option<String> {
+    match arguments.get(name) {
+        None | Some(Value::Null) => None,
+        Some(Value::String(s)) => Some(s.clone()),
+        Some(value) => {
+            problems.push(format!("{}: must be a string, got {}", name, value));
+            None
+        }
+    }
+}
+
+/// Reads an optional string argument that must name one of `options`
+fn optional_choice_arg<T: Copy>(
+    arguments: &Map<String, Value>,
+    name: &str,
+    options: &[T],
+    name_of: fn(T) -> &'static str,
+    problems: &mut Vec<String>,
+) -> Option<T> {
+    let value = optional_string_arg(arguments, name, problems)?;
+    let choice = options.iter().copied().find(|&option| name_of(option) == value);
+    if choice.is_none() {
+        let allowed: Vec<&str> = options.iter().map(|&option| name_of(option)).collect();
+        problems.push(format!("{}: must be one of {}, got {:?}", name, allowed.join(", "), value));
+    }
+    choice
+}
+
+/// Reads an optional boolean argument
+fn optional_bool_arg(arguments: &Map<String, Value>, name: &str, problems: &mut Vec<String>) -> Option<bool> {
+    match arguments.get(name) {
+        None | Some(Value::Null) => None,
+        Some(Value::Bool(b)) => Some(*b),
+        Some(value) => {
+            problems.push(format!("{}: must be a boolean, got {}", name, value));
+            None
+        }
+    }
+}
+
+/// Reports every argument not in `known`; the model would otherwise assume
+/// it took effect
+fn unknown_args(arguments: &Map<String, Value>, known: &[&str], tool: &str, problems: &mut Vec<String>) {
+    for name in arguments.keys().filter(|name| !known.contains(&name.as_str())) {
+        problems.push(format!("{}: is not a parameter of {}", name, tool));
+    }
+}
+
+/// Tool result explaining which arguments were rejected
+fn invalid_arguments(tool: &str, problems: &[String]) -> Value {
+    let mut message = format!("Invalid arguments for {}:", tool);
+    for problem in problems {
+        message.push_str("\n- ");
+        message.push_str(problem);
+    }
+    error_result(message)
+}
+
+/// Successful tool result with a single text block
+fn text_result(text: String) -> Value {
+    json!({
+        "content": [
+            {
+                "type": "text",
+                "text": text
+            }
+        ]
+    })
+}
+
+/// Failed tool result the model gets to see
+fn error_result(text: String) -> Value {
+    let mut result = text_result(text);
+    result["isError"] = json!(true);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-05-04T12:00:00Z
+    const NOW: i64 = 1_714_824_000;
+
+    #[test]
+    fn get_weather_defaults() {
+        let args = GetWeatherArgs::parse(&json!({"latitude": 59.91, "longitude": 10.75}), Language::En).unwrap();
+        assert_eq!(args.forecast_type, ForecastType::Current);
+        assert!(!args.include_sun_moon);
+        assert_eq!(args.shared.location_name, "Unknown Location");
+        assert_eq!(args.shared.timezone.name(), "UTC+01:00");
+        assert!(args.shared.timezone.is_estimated());
+        assert_eq!(args.shared.units.temperature, TemperatureUnit::Celsius);
+    }
+
+    #[test]
+    fn get_weather_lists_every_problem() {
+        let problems = GetWeatherArgs::parse(
+            &json!({
+                "latitude": 91,
+                "longitude": "east",
+                "altitude": 1200.5,
+                "forecast_type": "monthly",
+                "language": "de",
+                "units": "si",
+                "detailed": "yes"
+            }),
+            Language::En,
+        )
+        .err()
+        .unwrap();
+        let fields: Vec<&str> = problems.iter().filter_map(|p| p.split(':').next()).collect();
+        assert_eq!(
+            fields,
+            ["latitude", "longitude", "altitude", "detailed", "language", "units", "forecast_type"]
+        );
+        assert!(GetWeatherArgs::parse(&json!([59.91, 10.75]), Language::En).is_err());
+    }
+
+    #[test]
+    fn unit_overrides_apply_on_top_of_the_preset() {
+        let args = GetWeatherArgs::parse(
+            &json!({
+                "latitude": 51.5,
+                "longitude": -0.1,
+                "units": "imperial",
+                "temperature_unit": "celsius",
+                "wind_speed_unit": "beaufort"
+            }),
+            Language::En,
+        )
+        .unwrap();
+        let units = args.shared.units;
+        assert_eq!(units.temperature, TemperatureUnit::Celsius);
+        assert_eq!(units.wind_speed, SpeedUnit::Beaufort);
+        assert_eq!(units.precipitation, PrecipitationUnit::Inches);
+        assert_eq!(units.pressure, PressureUnit::InchesOfMercury);
+    }
+
+    #[test]
+    fn hourly_window_defaults_to_twelve_hours_from_now() {
+        let args = HourlyArgs::parse(&json!({"latitude": 59.91, "longitude": 10.75}), Language::En, NOW).unwrap();
+        assert_eq!((args.start, args.end), (NOW, NOW + 12 * 3600));
+
+        let args = HourlyArgs::parse(
+            &json!({
+                "latitude": 59.91,
+                "longitude": 10.75,
+                "timezone": "Europe/Oslo",
+                "start": "2024-05-04T18:00",
+                "end": "+1d"
+            }),
+            Language::En,
+            NOW,
+        )
+        .unwrap();
+        assert_eq!(args.start, NOW + 4 * 3600);
+        assert_eq!(args.end, NOW + 24 * 3600);
+    }
+
+    #[test]
+    fn hourly_window_must_be_valid() {
+        let arguments = json!({"latitude": 59.91, "longitude": 10.75, "start": "+6h", "end": "+2h"});
+        assert_eq!(
+            HourlyArgs::parse(&arguments, Language::En, NOW).err().unwrap(),
+            ["end: must be after start"]
+        );
+        let arguments = json!({"latitude": 59.91, "longitude": 10.75, "start": "tomorrow-ish"});
+        let problems = HourlyArgs::parse(&arguments, Language::En, NOW).err().unwrap();
+        assert!(problems[0].starts_with("start:"));
+    }
+
+    #[test]
+    fn hourly_window_stays_within_the_forecast_horizon() {
+        let window = |start: &str, end: &str| {
+            let arguments = json!({"latitude": 59.91, "longitude": 10.75, "start": start, "end": end});
+            HourlyArgs::parse(&arguments, Language::En, NOW).map(|args| (args.start, args.end))
+        };
+        assert_eq!(window("+9d", "+10d"), Ok((NOW + 9 * 86_400, NOW + 10 * 86_400)));
+        assert_eq!(window("-1d", "now"), Ok((NOW - 86_400, NOW)));
+
+        assert_eq!(window("now", "+11d").err().unwrap(), ["end: must be within 10 days of now, got \"+11d\""]);
+        assert_eq!(window("2024-06-01", "+1h").err().unwrap()[0], "start: must be within 10 days of now, got \"2024-06-01\"");
+        // Used to overflow and panic
+        let problems = window("+106751991167300d", "+1h").err().unwrap();
+        assert!(problems[0].starts_with("start: expected"), "{:?}", problems);
+        let arguments = json!({"latitude": 59.91, "longitude": 10.75, "start": "+106751991167300d"});
+        assert!(HourlyArgs::parse(&arguments, Language::En, NOW).is_err());
+    }
+
+    fn property_names(object: &Value) -> Vec<&str> {
+        let mut names: Vec<&str> = object.as_object().unwrap().keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn get_weather_accepts_its_schema_properties() {
+        let tools = list_tools(&Session::new());
+        let mut arguments = json!({
+            "latitude": 61.64,
+            "longitude": 8.31,
+            "altitude": 2469,
+            "location_name": "Galdhøpiggen",
+            "timezone": "Europe/Oslo",
+            "detailed": true,
+            "language": "nb",
+            "units": "uk",
+            "temperature_unit": "fahrenheit",
+            "wind_speed_unit": "knots",
+            "precipitation_unit": "in",
+            "pressure_unit": "mmHg"
+        });
+        assert_eq!(property_names(&arguments), property_names(&shared_properties()));
+
+        arguments["forecast_type"] = json!("weekly");
+        arguments["include_sun_moon"] = json!(true);
+        assert_eq!(property_names(&arguments), property_names(&tools["tools"][0]["inputSchema"]["properties"]));
+        let args = GetWeatherArgs::parse(&arguments, Language::En).unwrap();
+        assert_eq!(args.forecast_type, ForecastType::Weekly);
+        assert_eq!(args.shared.altitude, Some(2469));
+    }
+
+    #[test]
+    fn get_weather_rejects_arguments_it_does_not_use() {
+        let arguments = json!({"latitude": 59.91, "longitude": 10.75, "start": "+6h", "end": "+12h"});
+        assert_eq!(
+            GetWeatherArgs::parse(&arguments, Language::En).err().unwrap(),
+            ["start: is not a parameter of get_weather", "end: is not a parameter of get_weather"]
+        );
+    }
+
+    #[test]
+    fn hourly_accepts_its_schema_properties() {
+        let tools = list_tools(&Session::new());
+        let arguments = json!({
+            "latitude": 59.91,
+            "longitude": 10.75,
+            "altitude": 20,
+            "location_name": "Oslo",
+            "timezone": "Europe/Oslo",
+            "detailed": false,
+            "language": "en",
+            "units": "metric",
+            "temperature_unit": "celsius",
+            "wind_speed_unit": "m/s",
+            "precipitation_unit": "mm",
+            "pressure_unit": "hPa",
+            "start": "+1h",
+            "end": "+3h"
+        });
+        assert_eq!(property_names(&arguments), property_names(&tools["tools"][1]["inputSchema"]["properties"]));
+        let args = HourlyArgs::parse(&arguments, Language::En, NOW).unwrap();
+        assert_eq!((args.start, args.end), (NOW + 3600, NOW + 3 * 3600));
+    }
+
+    #[test]
+    fn hourly_rejects_arguments_it_does_not_use() {
+        let arguments = json!({"latitude": 59.91, "longitude": 10.75, "include_sun_moon": true, "period": "6h"});
+        assert_eq!(
+            HourlyArgs::parse(&arguments, Language::En, NOW).err().unwrap(),
+            [
+                "include_sun_moon: is not a parameter of get_hourly_forecast",
+                "period: is not a parameter of get_hourly_forecast"
+            ]
+        );
+    }
+
+    #[test]
+    fn sun_moon_accepts_its_schema_properties() {
+        let tools = list_tools(&Session::new());
+        let schema = &tools["tools"][2]["inputSchema"]["properties"];
+        let arguments = json!({
+            "latitude": 69.65,
+            "longitude": 18.96,
+            "location_name": "Tromsø",
+            "timezone": "Europe/Oslo",
+            "language": "nb",
+            "date": "2024-06-21",
+            "days": 7
+        });
+        assert_eq!(property_names(&arguments), property_names(schema));
+
+        let args = SunMoonArgs::parse(&arguments, Language::En, NOW).unwrap();
+        assert_eq!(args.start, "2024-06-21".parse::<Date>().unwrap());
+        assert_eq!(args.days, 7);
+    }
+
+    #[test]
+    fn sun_moon_rejects_arguments_it_does_not_use() {
+        let arguments = json!({"latitude": 59.91, "longitude": 10.75, "altitude": 1200, "detailed": true, "days": 8});
+        assert_eq!(
+            SunMoonArgs::parse(&arguments, Language::En, NOW).err().unwrap(),
+            [
+                "days: must be between 1 and 7, got 8",
+                "altitude: is not a parameter of get_sun_moon",
+                "detailed: is not a parameter of get_sun_moon"
+            ]
+        );
+    }
+
+    #[test]
+    fn sun_moon_date_stays_within_a_year() {
+        let start = |date: &str| {
+            let arguments = json!({"latitude": 59.91, "longitude": 10.75, "timezone": "UTC", "date": date});
+            SunMoonArgs::parse(&arguments, Language::En, NOW).map(|args| args.start.to_string())
+        };
+        assert_eq!(start("tomorrow").unwrap(), "2024-05-05");
+        assert_eq!(start("+365d").unwrap(), "2025-05-04");
+        assert_eq!(start("2023-05-04").unwrap(), "2023-05-04");
+
+        assert_eq!(start("2026-01-01").err().unwrap(), ["date: must be within a year of today, got \"2026-01-01\""]);
+        assert_eq!(start("-400d").err().unwrap(), ["date: must be within a year of today, got \"-400d\""]);
+        // Used to overflow and panic
+        for date in ["+106751991167300d", "-106751991167300d"] {
+            let problems = start(date).err().unwrap();
+            assert!(problems[0].starts_with("date: "), "{:?}", problems);
+        }
+    }
+
+    #[test]
+    fn nowcast_accepts_its_schema_properties() {
+        let tools = list_tools(&Session::new());
+        let schema = &tools["tools"][3]["inputSchema"]["properties"];
+        let arguments = json!({
+            "latitude": 59.91,
+            "longitude": 10.75,
+            "location_name": "Oslo",
+            "timezone": "Europe/Oslo",
+            "language": "nb",
+            "units": "imperial",
+            "temperature_unit": "celsius",
+            "wind_speed_unit": "km/h",
+            "precipitation_unit": "mm"
+        });
+        assert_eq!(property_names(&arguments), property_names(schema));
+
+        let args = NowcastArgs::parse(&arguments, Language::En).unwrap();
+        assert_eq!(args.units.temperature, TemperatureUnit::Celsius);
+        assert_eq!(args.units.precipitation, PrecipitationUnit::Millimeters);
+    }
+
+    #[test]
+    fn nowcast_rejects_arguments_it_does_not_use() {
+        let arguments = json!({"latitude": 59.91, "longitude": 10.75, "pressure_unit": "hPa", "detailed": true});
+        assert_eq!(
+            NowcastArgs::parse(&arguments, Language::En).err().unwrap(),
+            [
+                "pressure_unit: is not a parameter of get_nowcast",
+                "detailed: is not a parameter of get_nowcast"
+            ]
+        );
+    }
+}
@@ -0,0 +1,316 @@
+use serde::Serialize;
+
+/// Named combination of units, selected with the `units` tool argument
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnitPreset {
+    /// °C, m/s, mm and hPa, as met.no delivers them
+    Metric,
+    /// °F, mph, inches and inHg
+    Imperial,
+    /// °C, mph, mm and hPa, as British forecasts use
+    Uk,
+}
+
+impl UnitPreset {
+    pub const ALL: [UnitPreset; 3] = [Self::Metric, Self::Imperial, Self::Uk];
+
+    /// Name used in tool arguments
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Metric => "metric",
+            Self::Imperial => "imperial",
+            Self::Uk => "uk",
+        }
+    }
+
+    pub fn temperature(self) -> TemperatureUnit {
+        match self {
+            Self::Metric | Self::Uk => TemperatureUnit::Celsius,
+            Self::Imperial => TemperatureUnit::Fahrenheit,
+        }
+    }
+
+    pub fn wind_speed(self) -> SpeedUnit {
+        match self {
+            Self::Metric => SpeedUnit::MetersPerSecond,
+            Self::Imperial | Self::Uk => SpeedUnit::MilesPerHour,
+        }
+    }
+
+    pub fn precipitation(self) -> PrecipitationUnit {
+        match self {
+            Self::Metric | Self::Uk => PrecipitationUnit::Millimeters,
+            Self::Imperial => PrecipitationUnit::Inches,
+        }
+    }
+
+    pub fn pressure(self) -> PressureUnit {
+        match self {
+            Self::Metric | Self::Uk => PressureUnit::Hectopascals,
+            Self::Imperial => PressureUnit::InchesOfMercury,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum TemperatureUnit {
+    #[serde(rename = "celsius")]
+    Celsius,
+    #[serde(rename = "fahrenheit")]
+    Fahrenheit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum SpeedUnit {
+    #[serde(rename = "m/s")]
+    MetersPerSecond,
+    #[serde(rename = "km/h")]
+    KilometersPerHour,
+    #[serde(rename = "mph")]
+    MilesPerHour,
+    #[serde(rename = "knots")]
+    Knots,
+    /// Force on the Beaufort scale, 0–12
+    #[serde(rename = "beaufort")]
+    Beaufort,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum PrecipitationUnit {
+    #[serde(rename = "mm")]
+    Millimeters,
+    #[serde(rename = "in")]
+    Inches,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum PressureUnit {
+    #[serde(rename = "hPa")]
+    Hectopascals,
+    #[serde(rename = "inHg")]
+    InchesOfMercury,
+    #[serde(rename = "mmHg")]
+    MillimetersOfMercury,
+}
+
+impl TemperatureUnit {
+    pub const ALL: [TemperatureUnit; 2] = [Self::Celsius, Self::Fahrenheit];
+
+    /// Name used in tool arguments and the structured output
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Celsius => "celsius",
+            Self::Fahrenheit => "fahrenheit",
+        }
+    }
+
+    /// Converts from °C
+    pub fn convert(self, celsius: f64) -> f64 {
+        match self {
+            Self::Celsius => celsius,
+            Self::Fahrenheit => round(celsius * 9.0 / 5.0 + 32.0, 1),
+        }
+    }
+
+    /// Value without the unit, e.g. for the ends of a range
+    pub fn number(self, value: f64) -> String {
+        format!("{:.1}", value)
+    }
+
+    /// Value with its unit, such as "21.5°C"
+    pub fn format(self, value: f64) -> String {
+        match self {
+            Self::Celsius => format!("{:.1}°C", value),
+            Self::Fahrenheit => format!("{:.1}°F", value),
+        }
+    }
+}
+
+impl SpeedUnit {
+    pub const ALL: [SpeedUnit; 5] = [
+        Self::MetersPerSecond,
+        Self::KilometersPerHour,
+        Self::MilesPerHour,
+        Self::Knots,
+        Self::Beaufort,
+    ];
+
+    /// Name used in tool arguments and the structured output
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::MetersPerSecond => "m/s",
+            Self::KilometersPerHour => "km/h",
+            Self::MilesPerHour => "mph",
+            Self::Knots => "knots",
+            Self::Beaufort => "beaufort",
+        }
+    }
+
+    /// Converts from m/s
+    pub fn convert(self, meters_per_second: f64) -> f64 {
+        match self {
+            Self::MetersPerSecond => meters_per_second,
+            Self::KilometersPerHour => round(meters_per_second * 3.6, 1),
+            Self::MilesPerHour => round(meters_per_second / 0.44704, 1),
+            Self::Knots => round(meters_per_second * 3600.0 / 1852.0, 1),
+            Self::Beaufort => f64::from(beaufort(meters_per_second)),
+        }
+    }
+
+    /// Value with its unit, such as "4.2 m/s" or "3 Bft"
+    pub fn format(self, value: f64) -> String {
+        match self {
+            Self::MetersPerSecond => format!("{:.1} m/s", value),
+            Self::KilometersPerHour => format!("{:.1} km/h", value),
+            Self::MilesPerHour => format!("{:.1} mph", value),
+            Self::Knots => format!("{:.1} kn", value),
+            Self::Beaufort => format!("{:.0} Bft", value),
+        }
+    }
+}
+
+impl PrecipitationUnit {
+    pub const ALL: [PrecipitationUnit; 2] = [Self::Millimeters, Self::Inches];
+
+    /// Name used in tool arguments and the structured output
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Millimeters => "mm",
+            Self::Inches => "in",
+        }
+    }
+
+    /// Converts from mm
+    pub fn convert(self, millimeters: f64) -> f64 {
+        match self {
+            Self::Millimeters => millimeters,
+            Self::Inches => round(millimeters / 25.4, 2),
+        }
+    }
+
+    /// Value without the unit, e.g. for a row of rates
+    pub fn number(self, value: f64) -> String {
+        match self {
+            Self::Millimeters => format!("{:.1}", value),
+            Self::Inches => format!("{:.2}", value),
+        }
+    }
+
+    /// Value with its unit, such as "1.2 mm" or "0.05 in"
+    pub fn format(self, value: f64) -> String {
+        match self {
+            Self::Millimeters => format!("{:.1} mm", value),
+            Self::Inches => format!("{:.2} in", value),
+        }
+    }
+}
+
+impl PressureUnit {
+    pub const ALL: [PressureUnit; 3] = [Self::Hectopascals, Self::InchesOfMercury, Self::MillimetersOfMercury];
+
+    /// Name used in tool arguments and the structured output
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Hectopascals => "hPa",
+            Self::InchesOfMercury => "inHg",
+            Self::MillimetersOfMercury => "mmHg",
+        }
+    }
+
+    /// Converts from hPa
+    pub fn convert(self, hectopascals: f64) -> f64 {
+        match self {
+            Self::Hectopascals => hectopascals,
+            Self::InchesOfMercury => round(hectopascals / 33.8639, 2),
+            Self::MillimetersOfMercury => round(hectopascals / 1.333_22, 0),
+        }
+    }
+
+    /// Value with its unit, such as "1013 hPa" or "29.92 inHg"
+    pub fn format(self, value: f64) -> String {
+        match self {
+            Self::Hectopascals => format!("{:.0} hPa", value),
+            Self::InchesOfMercury => format!("{:.2} inHg", value),
+            Self::MillimetersOfMercury => format!("{:.0} mmHg", value),
+        }
+    }
+}
+
+/// Beaufort force for a wind speed in m/s, on the WMO scale
+pub fn beaufort(meters_per_second: f64) -> u8 {
+    // Lowest speed of forces 1 to 12
+    const LOWER_LIMITS: [f64; 12] = [0.3, 1.6, 3.4, 5.5, 8.0, 10.8, 13.9, 17.2, 20.8, 24.5, 28.5, 32.7];
+    LOWER_LIMITS.iter().take_while(|&&limit| meters_per_second >= limit).count() as u8
+}
+
+/// Rounds to the given number of decimals, so converted values do not carry
+/// floating-point noise into the output
+fn round(value: f64, decimals: i32) -> f64 {
+    let factor = 10f64.powi(decimals);
+    (value * factor).round() / factor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn beaufort_thresholds() {
+        let cases = [
+            (0.0, 0),
+            (0.29, 0),
+            (0.3, 1),
+            (1.6, 2),
+            (5.4, 3),
+            (5.5, 4),
+            (17.1, 7),
+            (17.2, 8),
+            (32.6, 11),
+            (32.7, 12),
+            (60.0, 12),
+        ];
+        for (speed, force) in cases {
+            assert_eq!(beaufort(speed), force, "{} m/s", speed);
+        }
+    }
+
+    #[test]
+    fn converts_temperatures() {
+        assert_eq!(TemperatureUnit::Fahrenheit.convert(0.0), 32.0);
+        assert_eq!(TemperatureUnit::Fahrenheit.convert(100.0), 212.0);
+        assert_eq!(TemperatureUnit::Fahrenheit.convert(-40.0), -40.0);
+        assert_eq!(TemperatureUnit::Fahrenheit.convert(21.3), 70.3);
+        assert_eq!(TemperatureUnit::Celsius.convert(21.3), 21.3);
+    }
+
+    #[test]
+    fn converts_wind_speeds() {
+        assert_eq!(SpeedUnit::MetersPerSecond.convert(10.0), 10.0);
+        assert_eq!(SpeedUnit::KilometersPerHour.convert(10.0), 36.0);
+        assert_eq!(SpeedUnit::MilesPerHour.convert(10.0), 22.4);
+        assert_eq!(SpeedUnit::Knots.convert(10.0), 19.4);
+        assert_eq!(SpeedUnit::Beaufort.convert(10.0), 5.0);
+    }
+
+    #[test]
+    fn converts_precipitation_and_pressure() {
+        assert_eq!(PrecipitationUnit::Inches.convert(25.4), 1.0);
+        assert_eq!(PrecipitationUnit::Inches.convert(1.2), 0.05);
+        assert_eq!(PressureUnit::InchesOfMercury.convert(1013.25), 29.92);
+        assert_eq!(PressureUnit::MillimetersOfMercury.convert(1013.25), 760.0);
+        assert_eq!(PressureUnit::Hectopascals.convert(1013.25), 1013.25);
+    }
+
+    #[test]
+    fn presets_and_formatting() {
+        assert_eq!(UnitPreset::Uk.temperature(), TemperatureUnit::Celsius);
+        assert_eq!(UnitPreset::Uk.wind_speed(), SpeedUnit::MilesPerHour);
+        assert_eq!(UnitPreset::Imperial.precipitation(), PrecipitationUnit::Inches);
+        assert_eq!(UnitPreset::Imperial.pressure(), PressureUnit::InchesOfMercury);
+
+        assert_eq!(TemperatureUnit::Fahrenheit.format(70.3), "70.3°F");
+        assert_eq!(SpeedUnit::Beaufort.format(5.0), "5 Bft");
+        assert_eq!(PrecipitationUnit::Inches.format(0.05), "0.05 in");
+        assert_eq!(PressureUnit::Hectopascals.format(1013.25), "1013 hPa");
+    }
+}
@@ -1,30 +1,369 @@
-use anyhow::{Result, anyhow};
-use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
+use jiff::ToSpan;
+use reqwest::header::{HeaderMap, HeaderName, EXPIRES, IF_MODIFIED_SINCE, LAST_MODIFIED, RETRY_AFTER};
+use reqwest::{Certificate, Proxy, Response, StatusCode, Url};
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
+use std::collections::hash_map::RandomState;
+use std::error::Error as _;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
+use thiserror::Error;
+use tokio::time::sleep;
+use tracing::{debug, warn};
 
-/// API base URL for YR.no weather data
-const API_BASE_URL: &str = "https://api.met.no/weatherapi/locationforecast/2.0/compact";
-/// Default User-Agent string for API requests
-const USER_AGENT_STR: &str = "yr-weather-mcp/0.1.0 (https://github.com/example/yr-weather-mcp)";
+use crate::astronomy::{self, MoonTimes, SunTimes};
+use crate::cache::{CachedResponse, ResponseCache};
+use crate::comfort::Comfort;
+use crate::forecast::{
+    CloudLayers, DailySummary, DayPart, Details, Event, Forecast, ForecastEntry, ForecastType, Location,
+    PeriodStats, PeriodSummary, Precipitation, Symbol, TimeWindow, Units, Wind, ZoneInfo,
+};
+use crate::i18n::Language;
+use crate::nowcast::{Nowcast, NowcastConditions, NowcastStep, PrecipitationOutlook, RadarCoverage};
+use crate::rate_limit::RateLimiter;
+use crate::session::Progress;
+use crate::sun_moon::{AstroSource, SunMoon, SunMoonDay, SunMoonEvents};
+use crate::symbol::WeatherSymbol;
+use crate::timezone::{self, Date, TimeZone};
+
+/// Default base URL of the met.no weather API
+pub const DEFAULT_BASE_URL: &str = "https://api.met.no/weatherapi";
+/// Path of the basic locationforecast product below the base URL
+const COMPACT_PATH: &str = "locationforecast/2.0/compact";
+/// Path of the locationforecast product with every variable
+const COMPLETE_PATH: &str = "locationforecast/2.0/complete";
+/// Path of the Sunrise product, whose `sun` and `moon` endpoints give rise
+/// and set times for one local day
+const SUNRISE_PATH: &str = "sunrise/3.0";
+/// Path of the radar-based precipitation nowcast, which only covers the
+/// Nordic countries
+const NOWCAST_PATH: &str = "nowcast/2.0/complete";
+/// Application name and version sent in the User-Agent
+const USER_AGENT_PRODUCT: &str = concat!("yr-weather-mcp/", env!("CARGO_PKG_VERSION"));
+/// Requests that may be sent back to back before the rate limiter kicks in
+const RATE_LIMIT_BURST: u32 = 10;
+/// Sustained request rate, well below the 20 requests/s met.no allows
+const RATE_LIMIT_PER_SECOND: f64 = 5.0;
+/// Longest `Retry-After` a tool call waits out before giving up
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(10);
+/// Back-off applied when a 429 carries no usable `Retry-After`
+const DEFAULT_RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(30);
+/// Attempts per request while met.no answers 429
+const MAX_RATE_LIMIT_ATTEMPTS: u32 = 2;
+/// Default limit for establishing a connection to met.no
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Default limit for a whole request, including reading the response body
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+/// Default number of retries after a transient failure
+const DEFAULT_MAX_RETRIES: u32 = 2;
+/// Delay before the first retry; doubled for each further one
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+/// Upper bound for a single retry delay
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(8);
+
+/// Why a forecast could not be produced.
+///
+/// The messages are shown to the model as tool errors, so they explain what
+/// happened and whether trying again makes sense.
+#[derive(Debug, Error)]
+pub enum WeatherError {
+    #[error("Failed to create HTTP client: {0}")]
+    ClientSetup(#[source] reqwest::Error),
+    #[error("Invalid met.no base URL {0:?}: must be an http or https URL")]
+    InvalidBaseUrl(String),
+    #[error("Failed to read CA bundle {}: {source}", path.display())]
+    CaBundle {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("Could not reach met.no ({0}). Check the network connection and try again.")]
+    Network(#[source] reqwest::Error),
+    #[error("{}", rate_limited_message(*.0))]
+    RateLimited(Option<Duration>),
+    #[error("met.no rejected the request parameters (HTTP {0}). Check that latitude is between -90 and 90, longitude between -180 and 180 and altitude, when given, is a whole number of meters.")]
+    BadRequest(u16),
+    #[error("met.no refused the request (HTTP {0}).")]
+    Rejected(u16),
+    #[error("met.no is having problems (HTTP {0}). This is usually temporary; try again in a few minutes.")]
+    Upstream(u16),
+    #[error("met.no returned data that could not be read: {0}")]
+    Parse(String),
+    #[error("{0}")]
+    NoData(&'static str),
+    #[error("The location is outside the area met.no's precipitation nowcast covers (the Nordic countries). Use get_hourly_forecast for precipitation there instead.")]
+    OutsideNowcastArea,
+}
+
+impl WeatherError {
+    /// Classifies an unsuccessful met.no response
+    fn from_status(status: StatusCode) -> Self {
+        match status.as_u16() {
+            429 => Self::RateLimited(None),
+            400 | 422 => Self::BadRequest(status.as_u16()),
+            code @ 500..=599 => Self::Upstream(code),
+            code => Self::Rejected(code),
+        }
+    }
+}
+
+/// Tells the model how long to hold off after a 429
+fn rate_limited_message(retry_after: Option<Duration>) -> String {
+    let wait = match retry_after {
+        Some(retry_after) => format!("Wait at least {} seconds", retry_after.as_secs().max(1)),
+        None => "Wait a while".to_string(),
+    };
+    format!(
+        "met.no is rate limiting requests (HTTP 429). {} before asking for weather again; do not retry right away.",
+        wait
+    )
+}
+
+pub type Result<T> = std::result::Result<T, WeatherError>;
+
+/// What a forecast lookup asks met.no for
+pub struct ForecastRequest<'a> {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Ground height in meters; met.no falls back to its terrain model when
+    /// absent, which can be far off in mountains
+    pub altitude: Option<i32>,
+    pub location_name: &'a str,
+    pub forecast_type: ForecastType,
+    /// Use the `complete` product, which adds dew point, fog, cloud layers,
+    /// UV index, gusts, temperature range and precipitation probability to
+    /// every entry
+    pub detailed: bool,
+    /// Zone whose calendar days the daily views follow
+    pub timezone: &'a TimeZone,
+    /// Start and end in Unix seconds of the steps the hourly view lists
+    pub window: Option<(i64, i64)>,
+    pub language: Language,
+    /// Units the values are converted into
+    pub units: Units,
+    /// Add sunrise, sunset and moon phase to the days of daily views
+    pub sun_moon: bool,
+}
+
+/// What a sun and moon lookup asks for
+pub struct SunMoonRequest<'a> {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub location_name: &'a str,
+    /// Zone whose calendar days are described
+    pub timezone: &'a TimeZone,
+    /// First local day to describe
+    pub start: Date,
+    pub days: u32,
+    /// Local date today, which day labels are relative to
+    pub today: Date,
+    pub language: Language,
+}
+
+/// What a precipitation nowcast lookup asks for
+pub struct NowcastRequest<'a> {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub location_name: &'a str,
+    /// Zone the local times are given in
+    pub timezone: &'a TimeZone,
+    pub language: Language,
+    /// Units the values are converted into
+    pub units: Units,
+}
 
 /// Client for fetching weather data from YR.no API
 pub struct WeatherClient {
     client: reqwest::Client,
+    /// met.no API base URL, without a trailing slash
+    base_url: String,
+    cache: ResponseCache,
+    /// Shared by all tool calls so concurrent requests stay within met.no's limits
+    rate_limiter: RateLimiter,
+    /// Whether the 203 deprecation notice has been logged already
+    deprecation_warned: AtomicBool,
+    max_retries: u32,
+    /// Language used when a tool call does not ask for one
+    language: Language,
+}
+
+/// Configures and creates a [`WeatherClient`]
+pub struct WeatherClientBuilder {
+    connect_timeout: Duration,
+    request_timeout: Duration,
+    max_retries: u32,
+    cache: ResponseCache,
+    user_agent: Option<String>,
+    contact: Option<String>,
+    base_url: String,
+    proxy: Option<String>,
+    ca_bundle: Option<PathBuf>,
+    language: Language,
+}
+
+impl Default for WeatherClientBuilder {
+    fn default() -> Self {
+        Self {
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            max_retries: DEFAULT_MAX_RETRIES,
+            cache: ResponseCache::default(),
+            user_agent: None,
+            contact: None,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            proxy: None,
+            ca_bundle: None,
+            language: Language::default(),
+        }
+    }
+}
+
+impl WeatherClientBuilder {
+    /// Limits how long establishing a connection may take
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Limits how long a whole request may take, from connecting until the
+    /// response body has been read, so a stalled connection cannot hang a
+    /// tool call
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// Sets how many times a request is retried after a connection failure
+    /// or a 502/503/504 response
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Replaces the default in-memory response cache
+    pub fn cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Sets the contact address (e-mail or URL) met.no can use to reach
+    /// whoever runs this server; it is added to the User-Agent
+    pub fn contact(mut self, contact: impl Into<String>) -> Self {
+        self.contact = Some(contact.into());
+        self
+    }
+
+    /// Replaces the generated User-Agent entirely
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Sends requests to another met.no-compatible API, such as a local
+    /// mock server
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Routes all requests through a proxy
+    pub fn proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.proxy = Some(proxy.into());
+        self
+    }
+
+    /// Trusts the root certificates in a PEM file in addition to the
+    /// system ones, e.g. for a TLS-intercepting proxy
+    pub fn ca_bundle(mut self, path: impl Into<PathBuf>) -> Self {
+        self.ca_bundle = Some(path.into());
+        self
+    }
+
+    /// Language of tool results that do not ask for one
+    pub fn language(mut self, language: Language) -> Self {
+        self.language = language;
+        self
+    }
+
+    /// User-Agent identifying this application and, if known, its operator
+    fn user_agent_header(&self) -> String {
+        match (&self.user_agent, &self.contact) {
+            (Some(user_agent), _) => user_agent.clone(),
+            (None, Some(contact)) => format!("{} {}", USER_AGENT_PRODUCT, contact),
+            (None, None) => USER_AGENT_PRODUCT.to_string(),
+        }
+    }
+
+    /// Creates the client
+    pub fn build(self) -> Result<WeatherClient> {
+        let base_url = self.base_url.trim_end_matches('/').to_string();
+        match Url::parse(&base_url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            _ => return Err(WeatherError::InvalidBaseUrl(self.base_url)),
+        }
+
+        if self.user_agent.is_none() && self.contact.is_none() {
+            warn!("No contact address configured; met.no may block requests that don't identify their operator (set --contact)");
+        }
+
+        let mut builder = reqwest::Client::builder()
+            .user_agent(self.user_agent_header())
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.request_timeout);
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(Proxy::all(proxy).map_err(WeatherError::ClientSetup)?);
+        }
+        if let Some(path) = &self.ca_bundle {
+            let pem = std::fs::read(path).map_err(|source| WeatherError::CaBundle {
+                path: path.clone(),
+                source,
+            })?;
+            for certificate in Certificate::from_pem_bundle(&pem).map_err(WeatherError::ClientSetup)? {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        let client = builder.build().map_err(WeatherError::ClientSetup)?;
+
+        Ok(WeatherClient {
+            client,
+            base_url,
+            cache: self.cache,
+            rate_limiter: RateLimiter::new(RATE_LIMIT_BURST, RATE_LIMIT_PER_SECOND),
+            deprecation_warned: AtomicBool::new(false),
+            max_retries: self.max_retries,
+            language: self.language,
+        })
+    }
 }
 
 #[derive(Debug, Deserialize)]
 struct WeatherResponse {
+    geometry: Option<Geometry>,
     properties: Properties,
 }
 
+#[derive(Debug, Deserialize)]
+struct Geometry {
+    /// Longitude, latitude and the altitude met.no computed the forecast for
+    coordinates: Vec<f64>,
+}
+
 #[derive(Debug, Deserialize)]
 struct Properties {
+    meta: Option<Meta>,
     timeseries: Vec<TimeSeries>,
 }
 
+#[derive(Debug, Deserialize)]
+struct Meta {
+    updated_at: String,
+}
+
 #[derive(Debug, Deserialize)]
 struct TimeSeries {
-    #[allow(dead_code)]
     time: String,
     data: TimeSeriesData,
 }
@@ -51,6 +390,14 @@ struct InstantDetails {
     air_pressure_at_sea_level: f64,
     cloud_area_fraction: f64,
     wind_from_direction: f64,
+    // Only in the complete product
+    dew_point_temperature: Option<f64>,
+    fog_area_fraction: Option<f64>,
+    cloud_area_fraction_low: Option<f64>,
+    cloud_area_fraction_medium: Option<f64>,
+    cloud_area_fraction_high: Option<f64>,
+    ultraviolet_index_clear_sky: Option<f64>,
+    wind_speed_of_gust: Option<f64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -64,224 +411,1477 @@ struct Summary {
     symbol_code: String,
 }
 
+/// Document of the Sunrise API's `sun` endpoint
+#[derive(Debug, Deserialize)]
+struct SunResponse {
+    properties: SunProperties,
+}
+
+#[derive(Debug, Deserialize)]
+struct SunProperties {
+    sunrise: Option<SunriseEvent>,
+    sunset: Option<SunriseEvent>,
+    solarnoon: Option<SunriseEvent>,
+}
+
+/// Document of the Sunrise API's `moon` endpoint
+#[derive(Debug, Deserialize)]
+struct MoonResponse {
+    properties: MoonProperties,
+}
+
+#[derive(Debug, Deserialize)]
+struct MoonProperties {
+    moonrise: Option<SunriseEvent>,
+    moonset: Option<SunriseEvent>,
+    /// 0 at new moon, 180 at full moon
+    moonphase: f64,
+}
+
+/// A rise, set or culmination; `time` is null when it does not happen
+/// that day
+#[derive(Debug, Deserialize)]
+struct SunriseEvent {
+    time: Option<String>,
+    /// Only given for culminations
+    disc_centre_elevation: Option<f64>,
+}
+
+/// Document of the nowcast product
+#[derive(Debug, Deserialize)]
+struct NowcastResponse {
+    geometry: Option<Geometry>,
+    properties: NowcastProperties,
+}
+
+#[derive(Debug, Deserialize)]
+struct NowcastProperties {
+    meta: Option<NowcastMeta>,
+    timeseries: Vec<NowcastTimeSeries>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NowcastMeta {
+    updated_at: Option<String>,
+    /// "ok", "temporarily unavailable" or "no coverage"
+    radar_coverage: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NowcastTimeSeries {
+    time: String,
+    data: NowcastData,
+}
+
+#[derive(Debug, Deserialize)]
+struct NowcastData {
+    instant: NowcastInstant,
+    #[serde(rename = "next_1_hours")]
+    next_1_hours: Option<NextHours>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NowcastInstant {
+    details: NowcastDetails,
+}
+
+/// Only the first step carries more than the precipitation rate
+#[derive(Debug, Deserialize)]
+struct NowcastDetails {
+    precipitation_rate: Option<f64>,
+    air_temperature: Option<f64>,
+    relative_humidity: Option<f64>,
+    wind_speed: Option<f64>,
+    wind_from_direction: Option<f64>,
+    wind_speed_of_gust: Option<f64>,
+}
+
 #[derive(Debug, Deserialize)]
 struct NextHoursDetails {
     precipitation_amount: Option<f64>,
+    // Only in the complete product
+    probability_of_precipitation: Option<f64>,
+    probability_of_thunder: Option<f64>,
+    air_temperature_min: Option<f64>,
+    air_temperature_max: Option<f64>,
 }
 
 impl WeatherClient {
-    /// Creates a new WeatherClient with appropriate headers
-    pub fn new() -> Result<Self> {
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            USER_AGENT,
-            HeaderValue::from_static(USER_AGENT_STR),
-        );
-        
-        let client = reqwest::Client::builder()
-            .default_headers(headers)
-            .build()
-            .map_err(|e| anyhow!("Failed to create HTTP client: {}", e))?;
-            
-        Ok(Self { client })
+    /// Starts configuring a WeatherClient
+    pub fn builder() -> WeatherClientBuilder {
+        WeatherClientBuilder::default()
     }
-    
-    /// Fetches weather data by coordinates and returns formatted string
-    pub async fn get_weather_by_coords(&self, lat: f64, lon: f64, location_name: &str, forecast_type: &str) -> Result<String> {
-        let url = format!(
-            "{}?lat={:.4}&lon={:.4}",
-            API_BASE_URL, lat, lon
-        );
-        
-        let response = self.client
-            .get(&url)
-            .send()
-            .await?
-            .error_for_status()?
-            .json::<WeatherResponse>()
-            .await?;
-        
-        match forecast_type {
-            "current" => self.format_current_weather(&response, lat, lon, location_name).await,
-            "tomorrow" => self.format_tomorrow_weather(&response, lat, lon, location_name).await,
-            "weekly" => self.format_weekly_forecast(&response, lat, lon, location_name).await,
-            _ => self.format_current_weather(&response, lat, lon, location_name).await,
+
+    /// Language of tool results that do not ask for one
+    pub fn default_language(&self) -> Language {
+        self.language
+    }
+
+    /// Fetches and decodes a met.no JSON document, answering from the cache
+    /// while it is fresh and revalidating with `If-Modified-Since` once it
+    /// expires, as the met.no terms of service require
+    async fn fetch<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+        let cached = self.cache.get(url).await;
+        if let Some(cached) = cached.as_ref().filter(|c| c.is_fresh()) {
+            debug!("Serving {} from cache", url);
+            return parse(&cached.body);
+        }
+
+        let if_modified_since = cached.as_ref().and_then(|c| c.last_modified.as_deref());
+        let response = self.send(url, if_modified_since).await?;
+        let status = response.status();
+        // Without an Expires header the entry is stale at once and only
+        // useful for revalidation
+        let expires = header_str(response.headers(), EXPIRES)
+            .and_then(|value| httpdate::parse_http_date(&value).ok())
+            .unwrap_or_else(SystemTime::now);
+        let last_modified = header_str(response.headers(), LAST_MODIFIED);
+
+        if status == StatusCode::NOT_MODIFIED {
+            if let Some(mut cached) = cached {
+                debug!("{} not modified, reusing cached response", url);
+                cached.expires = expires;
+                cached.last_modified = last_modified.or(cached.last_modified);
+                let document = parse(&cached.body);
+                self.cache.insert(url.to_string(), cached).await;
+                return document;
+            }
+        }
+
+        if !status.is_success() {
+            return Err(WeatherError::from_status(status));
+        }
+        if status == StatusCode::NON_AUTHORITATIVE_INFORMATION && !self.deprecation_warned.swap(true, Ordering::Relaxed) {
+            warn!(
+                "met.no reports that {} is deprecated (HTTP 203); the product may be removed soon, check the met.no changelog",
+                url
+            );
         }
+
+        let body = response.text().await.map_err(WeatherError::Network)?;
+        let document = parse(&body)?;
+        self.cache
+            .insert(url.to_string(), CachedResponse { body, expires, last_modified })
+            .await;
+        Ok(document)
     }
-    
-    /// Formats current weather data
-    async fn format_current_weather(&self, response: &WeatherResponse, lat: f64, lon: f64, location_name: &str) -> Result<String> {
-        let current = response.properties.timeseries
-            .first()
-            .ok_or_else(|| anyhow!("No weather data available"))?;
-        
-        let details = &current.data.instant.details;
-        
-        let weather_symbol = current.data.next_1_hours
-            .as_ref()
-            .or(current.data.next_6_hours.as_ref())
-            .map_or("unknown", |n| n.summary.symbol_code.as_str());
-        
-        let weather_desc = get_weather_description(weather_symbol);
-        
-        let precipitation = current.data.next_1_hours
-            .as_ref()
-            .and_then(|n| n.details.as_ref())
-            .and_then(|d| d.precipitation_amount)
-            .unwrap_or(0.0);
-        
-        let wind_direction = get_wind_direction(details.wind_from_direction);
-        
-        let result = format!(
-            "🌍 **Current Weather**\n\
-            📍 **Location:** {}\n\
-            🗺️ **Coordinates:** {:.4}°, {:.4}°\n\n\
-            🌡️ **Temperature:** {:.1}°C\n\
-            ☁️ **Condition:** {}\n\
-            💧 **Humidity:** {:.0}%\n\
-            🌬️ **Wind:** {:.1} m/s from {}\n\
-            🌧️ **Precipitation (1h):** {:.1} mm\n\
-            ☁️ **Cloud coverage:** {:.0}%\n\
-            🔵 **Air pressure:** {:.0} hPa\n\n\
-            *Data from YR.no (Norwegian Meteorological Institute)*",
-            location_name,
-            lat,
-            lon,
-            details.air_temperature,
-            weather_desc,
-            details.relative_humidity,
-            details.wind_speed,
-            wind_direction,
-            precipitation,
-            details.cloud_area_fraction,
-            details.air_pressure_at_sea_level
-        );
-        
-        Ok(result)
+
+    /// Sends a GET through the rate limiter, retrying transient failures
+    /// with jittered exponential backoff and waiting out short `Retry-After`
+    /// back-offs requested by met.no
+    async fn send(&self, url: &str, if_modified_since: Option<&str>) -> Result<Response> {
+        let mut attempt = 1;
+        let mut retries = 0;
+        loop {
+            self.rate_limiter
+                .acquire(MAX_RATE_LIMIT_WAIT)
+                .await
+                .map_err(|remaining| WeatherError::RateLimited(Some(remaining)))?;
+
+            let mut request = self.client.get(url);
+            if let Some(if_modified_since) = if_modified_since {
+                request = request.header(IF_MODIFIED_SINCE, if_modified_since);
+            }
+            let result = request.send().await;
+
+            let transient = match &result {
+                Ok(response) => is_transient_status(response.status()),
+                Err(e) => is_transient_error(e),
+            };
+            if transient && retries < self.max_retries {
+                retries += 1;
+                let delay = Self::retry_delay(retries);
+                match &result {
+                    Ok(response) => warn!("met.no answered {} for {}, retrying in {:?}", response.status(), url, delay),
+                    Err(e) => warn!("Request to {} failed ({}), retrying in {:?}", url, e, delay),
+                }
+                sleep(delay).await;
+                continue;
+            }
+
+            let response = result.map_err(WeatherError::Network)?;
+            if response.status() != StatusCode::TOO_MANY_REQUESTS {
+                return Ok(response);
+            }
+
+            let retry_after = retry_after(response.headers()).unwrap_or(DEFAULT_RATE_LIMIT_BACKOFF);
+            warn!("met.no rate limited {}, backing off for {}s", url, retry_after.as_secs());
+            // Holds back every other call too, not just this one
+            self.rate_limiter.pause_for(retry_after);
+
+            if attempt >= MAX_RATE_LIMIT_ATTEMPTS {
+                return Err(WeatherError::RateLimited(Some(retry_after)));
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Backoff before retry number `retry`: exponential in `retry`, capped,
+    /// with "equal jitter" so concurrent calls don't retry in lockstep
+    fn retry_delay(retry: u32) -> Duration {
+        let delay = RETRY_BASE_DELAY
+            .saturating_mul(1 << (retry - 1).min(16))
+            .min(MAX_RETRY_DELAY);
+        let half = delay / 2;
+        half + half.mul_f64(random_fraction())
     }
     
-    /// Formats tomorrow's weather data
-    async fn format_tomorrow_weather(&self, response: &WeatherResponse, lat: f64, lon: f64, location_name: &str) -> Result<String> {
-        let tomorrow_index = 24;
-        let tomorrow = response.properties.timeseries
-            .get(tomorrow_index)
-            .ok_or_else(|| anyhow!("No forecast data for tomorrow"))?;
-        
-        let details = &tomorrow.data.instant.details;
-        let weather_symbol = tomorrow.data.next_6_hours
-            .as_ref()
-            .map_or("unknown", |n| n.summary.symbol_code.as_str());
-        
-        let weather_desc = get_weather_description(weather_symbol);
-        let precipitation = tomorrow.data.next_6_hours
+    /// Fetches weather data by coordinates and builds the requested forecast
+    pub async fn get_weather_by_coords(&self, request: &ForecastRequest<'_>, progress: &Progress) -> Result<Forecast> {
+        let steps = if request.sun_moon { 3 } else { 2 };
+        progress.report(0, steps, "Fetching forecast from met.no");
+
+        let mut url = format!(
+            "{}/{}?lat={:.4}&lon={:.4}",
+            self.base_url,
+            if request.detailed { COMPLETE_PATH } else { COMPACT_PATH },
+            request.latitude,
+            request.longitude
+        );
+        if let Some(altitude) = request.altitude {
+            url.push_str(&format!("&altitude={}", altitude));
+        }
+
+        let response: WeatherResponse = self.fetch(&url).await?;
+
+        progress.report(1, steps, "Preparing forecast");
+
+        // met.no reports the height it actually used, which is its terrain
+        // model's unless we sent one
+        let altitude = response
+            .geometry
             .as_ref()
-            .and_then(|n| n.details.as_ref())
-            .and_then(|d| d.precipitation_amount)
-            .unwrap_or(0.0);
-        
-        let result = format!(
-            "📅 **Tomorrow's Weather**\n\
-            📍 **Location:** {}\n\
-            🗺️ **Coordinates:** {:.4}°, {:.4}°\n\n\
-            🌡️ **Temperature:** {:.1}°C\n\
-            ☁️ **Condition:** {}\n\
-            💧 **Humidity:** {:.0}%\n\
-            🌬️ **Wind:** {:.1} m/s\n\
-            🌧️ **Precipitation (6h):** {:.1} mm\n\n\
-            *Data from YR.no (Norwegian Meteorological Institute)*",
-            location_name,
-            lat,
-            lon,
-            details.air_temperature,
-            weather_desc,
-            details.relative_humidity,
-            details.wind_speed,
-            precipitation
+            .and_then(|g| g.coordinates.get(2).copied())
+            .or(request.altitude.map(f64::from));
+        let location = Location {
+            name: request.location_name.to_string(),
+            latitude: request.latitude,
+            longitude: request.longitude,
+            altitude,
+        };
+        let forecast_type = request.forecast_type;
+        let (tz, language) = (request.timezone, request.language);
+        let (entries, mut days) = match forecast_type {
+            ForecastType::Current => (current_entries(&response, language)?, Vec::new()),
+            ForecastType::Tomorrow => (Vec::new(), vec![tomorrow_day(&response, tz, language)?]),
+            ForecastType::Weekly => (Vec::new(), weekly_days(&response, tz, language)?),
+            ForecastType::Hourly => {
+                let (start, end) = request.window.unwrap_or((i64::MIN, i64::MAX));
+                (hourly_entries(&response, start, end, tz, language)?, Vec::new())
+            }
+        };
+
+        if request.sun_moon && !days.is_empty() {
+            progress.report(2, steps, "Fetching sun and moon data from met.no");
+            let dates: Vec<Date> = days.iter().filter_map(|day| day.date.parse().ok()).collect();
+            let events = self.sun_moon_events(request.latitude, request.longitude, tz, &dates).await;
+            for (day, events) in days.iter_mut().zip(events) {
+                day.sun_moon = Some(events);
+            }
+        }
+
+        let start = response
+            .properties
+            .timeseries
+            .first()
+            .and_then(|step| timezone::parse_utc(&step.time))
+            .unwrap_or_default();
+
+        progress.report(steps, steps, "Forecast ready");
+
+        Ok(Forecast {
+            location,
+            forecast_type,
+            language,
+            updated_at: response.properties.meta.map(|m| m.updated_at),
+            units: Units::default(),
+            timezone: zone_info(tz, start),
+            window: request.window.map(|(start, end)| TimeWindow {
+                start: timezone::format_utc(start),
+                end: timezone::format_utc(end),
+            }),
+            entries,
+            days,
+        }
+        .convert_units(request.units))
+    }
+
+    /// Sunrise, sunset and moon data for consecutive local days. Never
+    /// fails: without met.no the times are calculated offline.
+    pub async fn get_sun_moon(&self, request: &SunMoonRequest<'_>, progress: &Progress) -> SunMoon {
+        progress.report(0, 1, "Fetching sun and moon data from met.no");
+
+        let tz = request.timezone;
+        let dates: Vec<Date> = (0..request.days)
+            .map(|day| request.start.saturating_add(i64::from(day).days()))
+            .collect();
+        let events = self.sun_moon_events(request.latitude, request.longitude, tz, &dates).await;
+        let days = dates
+            .iter()
+            .zip(events)
+            .map(|(&date, events)| SunMoonDay {
+                label: day_label(date, request.today, request.language),
+                date: date.to_string(),
+                events,
+            })
+            .collect();
+
+        progress.report(1, 1, "Sun and moon data ready");
+
+        SunMoon {
+            location: Location {
+                name: request.location_name.to_string(),
+                latitude: request.latitude,
+                longitude: request.longitude,
+                altitude: None,
+            },
+            language: request.language,
+            timezone: zone_info(tz, tz.timestamp(request.start, 0)),
+            days,
+        }
+    }
+
+    /// Fetches met.no's radar nowcast: precipitation every five minutes for
+    /// the next two hours, and when it starts or stops
+    pub async fn get_nowcast(&self, request: &NowcastRequest<'_>, progress: &Progress) -> Result<Nowcast> {
+        progress.report(0, 2, "Fetching precipitation nowcast from met.no");
+
+        let url = format!(
+            "{}/{}?lat={:.4}&lon={:.4}",
+            self.base_url, NOWCAST_PATH, request.latitude, request.longitude
         );
-        
-        Ok(result)
+        // met.no answers 422 for coordinates outside its nowcast area
+        let response: NowcastResponse = self.fetch(&url).await.map_err(|e| match e {
+            WeatherError::BadRequest(422) => WeatherError::OutsideNowcastArea,
+            e => e,
+        })?;
+
+        progress.report(1, 2, "Preparing nowcast");
+
+        let (tz, language) = (request.timezone, request.language);
+        let mut rates = Vec::new();
+        for step in &response.properties.timeseries {
+            let Some(rate) = step.data.instant.details.precipitation_rate else {
+                continue;
+            };
+            let time = timezone::parse_utc(&step.time)
+                .ok_or_else(|| WeatherError::Parse(format!("invalid timestamp {:?}", step.time)))?;
+            rates.push((time, rate));
+        }
+        if rates.is_empty() {
+            return Err(WeatherError::NoData("No precipitation nowcast available"));
+        }
+
+        let meta = response.properties.meta;
+        let radar_coverage = RadarCoverage::from_metno(meta.as_ref().and_then(|m| m.radar_coverage.as_deref()));
+        let current = response
+            .properties
+            .timeseries
+            .first()
+            .and_then(|step| nowcast_conditions(step, language));
+        let outlook = PrecipitationOutlook::new(&rates, tz, language);
+
+        progress.report(2, 2, "Nowcast ready");
+
+        Ok(Nowcast {
+            location: Location {
+                name: request.location_name.to_string(),
+                latitude: request.latitude,
+                longitude: request.longitude,
+                altitude: response.geometry.and_then(|g| g.coordinates.get(2).copied()),
+            },
+            language,
+            updated_at: meta.and_then(|m| m.updated_at),
+            units: Units::default(),
+            timezone: zone_info(tz, rates[0].0),
+            radar_coverage,
+            current,
+            outlook,
+            steps: rates
+                .iter()
+                .map(|&(time, rate)| NowcastStep {
+                    at: Event::new(time, tz),
+                    precipitation_rate: rate,
+                })
+                .collect(),
+        }
+        .convert_units(request.units))
     }
-    
-    /// Formats weekly weather forecast
-    async fn format_weekly_forecast(&self, response: &WeatherResponse, lat: f64, lon: f64, location_name: &str) -> Result<String> {
-        let mut forecast = format!(
-            "📆 **7-Day Weather Forecast**\n\
-            📍 **Location:** {}\n\
-            🗺️ **Coordinates:** {:.4}°, {:.4}°\n\n",
-            location_name, lat, lon
+
+    /// Sun and moon over each of `dates`, from met.no while it answers and
+    /// calculated offline from the first failure on, so an unreachable
+    /// met.no costs one timeout rather than one per day
+    async fn sun_moon_events(&self, latitude: f64, longitude: f64, tz: &TimeZone, dates: &[Date]) -> Vec<SunMoonEvents> {
+        let mut online = true;
+        let mut events = Vec::with_capacity(dates.len());
+        for &date in dates {
+            let fetched = if online {
+                match self.fetch_sun_moon(latitude, longitude, date, tz).await {
+                    Ok(times) => Some(times),
+                    Err(e) => {
+                        warn!("Sunrise API unavailable, calculating sun and moon times offline: {}", e);
+                        online = false;
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+            events.push(match fetched {
+                Some((sun, moon)) => SunMoonEvents::new(AstroSource::MetNo, sun, moon, date, tz),
+                None => SunMoonEvents::new(
+                    AstroSource::Calculated,
+                    astronomy::sun_times(latitude, longitude, date, tz),
+                    astronomy::moon_times(latitude, longitude, date, tz),
+                    date,
+                    tz,
+                ),
+            });
+        }
+        events
+    }
+
+    /// Sun and moon times of one local day from met.no's Sunrise API
+    async fn fetch_sun_moon(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        date: Date,
+        tz: &TimeZone,
+    ) -> Result<(SunTimes, MoonTimes)> {
+        // The API splits days by the offset it is given, which has to be
+        // the one in effect at the location that day
+        let offset = timezone::format_offset(tz.offset_at(tz.timestamp(date, 12 * 3600)));
+        let query = format!(
+            "lat={:.4}&lon={:.4}&date={}&offset={}",
+            latitude,
+            longitude,
+            date,
+            offset.replace('+', "%2B")
         );
-        
-        for day in 0..7 {
-            let index = day * 24;
-            if let Some(entry) = response.properties.timeseries.get(index) {
-                let details = &entry.data.instant.details;
-                let weather_symbol = entry.data.next_6_hours
-                    .as_ref()
-                    .or(entry.data.next_1_hours.as_ref())
-                    .map_or("unknown", |n| n.summary.symbol_code.as_str());
-                
-                let weather_desc = get_weather_description(weather_symbol);
-                let precipitation = entry.data.next_6_hours
-                    .as_ref()
-                    .or(entry.data.next_1_hours.as_ref())
-                    .and_then(|n| n.details.as_ref())
-                    .and_then(|d| d.precipitation_amount)
-                    .unwrap_or(0.0);
-                
-                let day_name = match day {
-                    0 => "Today",
-                    1 => "Tomorrow",
-                    2 => "Day 3",
-                    3 => "Day 4",
-                    4 => "Day 5",
-                    5 => "Day 6",
-                    6 => "Day 7",
-                    _ => "Unknown",
-                };
-                
-                forecast.push_str(&format!(
-                    "**{}**: {} | 🌡️ {:.1}°C | 💧 {:.1}mm\n",
-                    day_name,
-                    weather_desc,
-                    details.air_temperature,
-                    precipitation
-                ));
+        let sun: SunResponse = self.fetch(&format!("{}/{}/sun?{}", self.base_url, SUNRISE_PATH, query)).await?;
+        let moon: MoonResponse = self.fetch(&format!("{}/{}/moon?{}", self.base_url, SUNRISE_PATH, query)).await?;
+
+        let solar_noon = sun.properties.solarnoon.as_ref();
+        let sun = SunTimes {
+            sunrise: event_time(sun.properties.sunrise.as_ref())?,
+            sunset: event_time(sun.properties.sunset.as_ref())?,
+            solar_noon: event_time(solar_noon)?,
+            // Needed to tell midnight sun from polar night, so fill it in
+            // when met.no leaves it out
+            noon_elevation: solar_noon
+                .and_then(|e| e.disc_centre_elevation)
+                .or_else(|| astronomy::sun_times(latitude, longitude, date, tz).noon_elevation),
+        };
+        let moon = MoonTimes {
+            moonrise: event_time(moon.properties.moonrise.as_ref())?,
+            moonset: event_time(moon.properties.moonset.as_ref())?,
+            phase: moon.properties.moonphase,
+        };
+        Ok((sun, moon))
+    }
+}
+
+/// Conditions at the first nowcast step; `None` when met.no leaves any of
+/// them out
+fn nowcast_conditions(step: &NowcastTimeSeries, language: Language) -> Option<NowcastConditions> {
+    let details = &step.data.instant.details;
+    let from_direction = details.wind_from_direction?;
+    let symbol_code = step.data.next_1_hours.as_ref()?.summary.symbol_code.as_str();
+    Some(NowcastConditions {
+        temperature: details.air_temperature?,
+        humidity: details.relative_humidity?,
+        wind: Wind {
+            speed: details.wind_speed?,
+            from_direction,
+            compass: get_wind_direction(from_direction, language),
+        },
+        wind_gust: details.wind_speed_of_gust,
+        symbol: weather_symbol(symbol_code, language),
+    })
+}
+
+/// Unix time of a Sunrise API event; `None` if it does not happen that day
+fn event_time(event: Option<&SunriseEvent>) -> Result<Option<i64>> {
+    let Some(time) = event.and_then(|e| e.time.as_deref()) else {
+        return Ok(None);
+    };
+    timezone::parse_utc(time)
+        .map(Some)
+        .ok_or_else(|| WeatherError::Parse(format!("invalid timestamp {:?}", time)))
+}
+
+/// The zone a result's local days refer to, with its offset at `at`
+fn zone_info(tz: &TimeZone, at: i64) -> ZoneInfo {
+    ZoneInfo {
+        name: tz.name().to_string(),
+        utc_offset: timezone::format_offset(tz.offset_at(at)),
+        estimated: tz.is_estimated(),
+    }
+}
+
+/// Decodes a met.no JSON document
+fn parse<T: DeserializeOwned>(body: &str) -> Result<T> {
+    serde_json::from_str(body).map_err(|e| WeatherError::Parse(e.to_string()))
+}
+
+/// Reads a header as a string, if present and valid
+fn header_str(headers: &HeaderMap, name: HeaderName) -> Option<String> {
+    headers.get(name).and_then(|v| v.to_str().ok()).map(str::to_string)
+}
+
+/// Whether a response status is worth retrying
+fn is_transient_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Whether a request failed in a way that a retry may fix: the connection
+/// could not be established or was dropped before a response arrived.
+/// Timeouts are not retried, since they already took the full budget.
+fn is_transient_error(error: &reqwest::Error) -> bool {
+    if error.is_timeout() {
+        return false;
+    }
+    if error.is_connect() {
+        return true;
+    }
+
+    let mut source = error.source();
+    while let Some(cause) = source {
+        if let Some(io_error) = cause.downcast_ref::<io::Error>() {
+            return matches!(
+                io_error.kind(),
+                io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::BrokenPipe
+                    | io::ErrorKind::UnexpectedEof
+            );
+        }
+        source = cause.source();
+    }
+    false
+}
+
+/// A random number in `[0, 1)` for retry jitter, drawn from the randomly
+/// seeded std hasher so no RNG crate is needed
+fn random_fraction() -> f64 {
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Reads a `Retry-After` header given either in seconds or as an HTTP date
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = header_str(headers, RETRY_AFTER)?;
+    match value.trim().parse::<u64>() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => {
+            let date = httpdate::parse_http_date(&value).ok()?;
+            Some(date.duration_since(SystemTime::now()).unwrap_or_default())
+        }
+    }
+}
+
+/// Current conditions from the first timeseries step
+fn current_entries(response: &WeatherResponse, language: Language) -> Result<Vec<ForecastEntry>> {
+    let current = response.properties.timeseries
+        .first()
+        .ok_or(WeatherError::NoData("No weather data available"))?;
+
+    // The hourly summary is the most precise one for "now"
+    let summary = current.data.next_1_hours
+        .as_ref()
+        .map(|n| (n, 1))
+        .or(current.data.next_6_hours.as_ref().map(|n| (n, 6)));
+
+    Ok(vec![forecast_entry(language.messages().now, current, summary, language)])
+}
+
+/// Every step whose period overlaps `start..end`, labelled with its local
+/// time
+fn hourly_entries(
+    response: &WeatherResponse,
+    start: i64,
+    end: i64,
+    tz: &TimeZone,
+    language: Language,
+) -> Result<Vec<ForecastEntry>> {
+    let entries: Vec<ForecastEntry> = samples(response)?
+        .iter()
+        .filter(|s| s.start < end && s.start + i64::from(s.hours) * 3600 > start)
+        .map(|s| {
+            let local = tz.local(s.start);
+            let label = format!(
+                "{} {:02}:{:02}",
+                language.date_label(local.date),
+                local.hour(),
+                local.seconds / 60 % 60
+            );
+            forecast_entry(&label, s.step, s.period.map(|n| (n, s.hours)), language)
+        })
+        .collect();
+
+    if entries.is_empty() {
+        return Err(WeatherError::NoData(
+            "No forecast data in the requested time window; met.no forecasts cover about 10 days ahead",
+        ));
+    }
+    Ok(entries)
+}
+
+/// Tomorrow in the location's time zone, split into night (00–06),
+/// morning (06–12), afternoon (12–18) and evening (18–24)
+fn tomorrow_day(response: &WeatherResponse, tz: &TimeZone, language: Language) -> Result<DailySummary> {
+    let samples = samples(response)?;
+    let today = samples
+        .first()
+        .map(|s| tz.local(s.start).date)
+        .ok_or(WeatherError::NoData("No weather data available"))?;
+    let tomorrow = today.saturating_add(1.day());
+
+    let in_day: Vec<&Sample> = samples.iter().filter(|s| tz.local(s.start).date == tomorrow).collect();
+    let stats = period_stats(&in_day, tz, language).ok_or(WeatherError::NoData("No forecast data for tomorrow"))?;
+
+    let periods = DayPart::ALL
+        .into_iter()
+        .filter_map(|part| {
+            let hours = part.start_hour()..part.start_hour() + 6;
+            let in_period: Vec<&Sample> = in_day
+                .iter()
+                .copied()
+                .filter(|s| hours.contains(&tz.local(s.start).hour()))
+                .collect();
+            let end = match hours.end {
+                24 => tz.timestamp(tomorrow.saturating_add(1.day()), 0),
+                end => tz.timestamp(tomorrow, end * 3600),
+            };
+            Some(PeriodSummary {
+                part,
+                label: part.label(language).to_string(),
+                start: timezone::format_utc(tz.timestamp(tomorrow, hours.start * 3600)),
+                end: timezone::format_utc(end),
+                stats: period_stats(&in_period, tz, language)?,
+            })
+        })
+        .collect();
+
+    Ok(DailySummary {
+        label: day_label(tomorrow, today, language),
+        date: tomorrow.to_string(),
+        stats,
+        periods,
+        sun_moon: None,
+    })
+}
+
+/// Summaries of up to seven local calendar days, starting with today
+fn weekly_days(response: &WeatherResponse, tz: &TimeZone, language: Language) -> Result<Vec<DailySummary>> {
+    let samples = samples(response)?;
+    let Some(today) = samples.first().map(|s| tz.local(s.start).date) else {
+        return Err(WeatherError::NoData("No weather data available"));
+    };
+
+    let mut days = Vec::new();
+    let mut date = today;
+    for _ in 0..7 {
+        let in_day: Vec<&Sample> = samples.iter().filter(|s| tz.local(s.start).date == date).collect();
+        if let Some(stats) = period_stats(&in_day, tz, language) {
+            days.push(DailySummary {
+                label: day_label(date, today, language),
+                date: date.to_string(),
+                stats,
+                periods: Vec::new(),
+                sun_moon: None,
+            });
+        }
+        date = date.saturating_add(1.day());
+    }
+
+    Ok(days)
+}
+
+/// "Today", "Tomorrow", or weekday and date such as "Sat 18 Oct"
+fn day_label(date: Date, today: Date, language: Language) -> String {
+    match (date - today).get_days() {
+        0 => language.messages().today.to_string(),
+        1 => language.messages().tomorrow.to_string(),
+        _ => language.date_label(date),
+    }
+}
+
+/// A timeseries step paired with the summary period that belongs to it
+struct Sample<'a> {
+    /// Start of the step in Unix seconds
+    start: i64,
+    step: &'a TimeSeries,
+    /// The `next_*_hours` block covering the time until the next step, so
+    /// that consecutive periods neither overlap nor leave gaps
+    period: Option<&'a NextHours>,
+    hours: u32,
+}
+
+/// Pairs every step with its period: hourly while met.no steps hourly, then
+/// 6-hourly once the timeseries thins out
+fn samples(response: &WeatherResponse) -> Result<Vec<Sample<'_>>> {
+    let timeseries = &response.properties.timeseries;
+    let starts = timeseries
+        .iter()
+        .map(|step| {
+            timezone::parse_utc(&step.time)
+                .ok_or_else(|| WeatherError::Parse(format!("invalid timestamp {:?}", step.time)))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(timeseries
+        .iter()
+        .enumerate()
+        .map(|(i, step)| {
+            let gap_hours = starts.get(i + 1).map(|next| (next - starts[i]) / 3600);
+            let one_hour = step.data.next_1_hours.as_ref().map(|n| (n, 1));
+            let six_hours = step.data.next_6_hours.as_ref().map(|n| (n, 6));
+            let period = match gap_hours {
+                Some(1) => one_hour.or(six_hours),
+                _ => six_hours.or(one_hour),
+            };
+            Sample {
+                start: starts[i],
+                step,
+                period: period.map(|(n, _)| n),
+                hours: period.map_or(1, |(_, hours)| hours),
+            }
+        })
+        .collect())
+}
+
+/// Aggregates the given samples; `None` if there are none
+fn period_stats(samples: &[&Sample], tz: &TimeZone, language: Language) -> Option<PeriodStats> {
+    let first = samples.first()?;
+    let instant = &first.step.data.instant.details;
+    let feels_like = comfort(first.step).feels_like;
+    let mut stats = PeriodStats {
+        temperature_min: instant.air_temperature,
+        temperature_max: instant.air_temperature,
+        precipitation: 0.0,
+        symbol: weather_symbol("unknown", language),
+        wind_speed_max: instant.wind_speed,
+        feels_like_min: feels_like,
+        feels_like_max: feels_like,
+        beaufort_max: 0,
+        hours: 0,
+        wind_gust_max: None,
+        precipitation_probability_max: None,
+    };
+    // Hours per symbol (ignoring the day/night variant), split by whether
+    // they fall in daylight, in order of first appearance
+    let mut symbols: Vec<(&str, &str, u32, u32)> = Vec::new();
+
+    for sample in samples {
+        let instant = &sample.step.data.instant.details;
+        stats.temperature_min = stats.temperature_min.min(instant.air_temperature);
+        stats.temperature_max = stats.temperature_max.max(instant.air_temperature);
+        stats.wind_speed_max = stats.wind_speed_max.max(instant.wind_speed);
+        stats.wind_gust_max = max_option(stats.wind_gust_max, instant.wind_speed_of_gust);
+        let comfort = comfort(sample.step);
+        stats.feels_like_min = stats.feels_like_min.min(comfort.feels_like);
+        stats.feels_like_max = stats.feels_like_max.max(comfort.feels_like);
+        stats.beaufort_max = stats.beaufort_max.max(comfort.beaufort);
+        stats.hours += sample.hours;
+
+        let Some(period) = sample.period else {
+            continue;
+        };
+        if let Some(details) = &period.details {
+            stats.precipitation += details.precipitation_amount.unwrap_or(0.0);
+            stats.precipitation_probability_max =
+                max_option(stats.precipitation_probability_max, details.probability_of_precipitation);
+            // The 6-hour extremes cover the hours between instants too
+            if sample.hours == 6 {
+                if let Some(min) = details.air_temperature_min {
+                    stats.temperature_min = stats.temperature_min.min(min);
+                }
+                if let Some(max) = details.air_temperature_max {
+                    stats.temperature_max = stats.temperature_max.max(max);
+                }
             }
         }
-        
-        forecast.push_str("\n*Data from YR.no (Norwegian Meteorological Institute)*");
-        Ok(forecast)
+
+        let code = period.summary.symbol_code.as_str();
+        let base = code.split('_').next().unwrap_or(code);
+        let daylight = (6..18).contains(&tz.local(sample.start).hour());
+        match symbols.iter_mut().find(|(b, ..)| *b == base) {
+            Some((_, symbol_code, day_hours, all_hours)) => {
+                // Show the daytime form of the symbol when there is one
+                if daylight && *day_hours == 0 {
+                    *symbol_code = code;
+                }
+                *day_hours += if daylight { sample.hours } else { 0 };
+                *all_hours += sample.hours;
+            }
+            None => symbols.push((base, code, if daylight { sample.hours } else { 0 }, sample.hours)),
+        }
     }
+
+    let has_daylight = symbols.iter().any(|&(_, _, day_hours, _)| day_hours > 0);
+    let dominant = symbols
+        .iter()
+        .rev()
+        .max_by_key(|&&(_, _, day_hours, all_hours)| if has_daylight { day_hours } else { all_hours });
+    if let Some(&(_, code, _, _)) = dominant {
+        stats.symbol = weather_symbol(code, language);
+    }
+
+    Some(stats)
 }
 
-/// Converts weather symbol code to human-readable description
-fn get_weather_description(symbol_code: &str) -> &str {
-    match symbol_code {
-        s if s.starts_with("clearsky") => "Clear sky ☀️",
-        s if s.starts_with("fair") => "Fair 🌤️",
-        s if s.starts_with("partlycloudy") => "Partly cloudy ⛅",
-        s if s.starts_with("cloudy") => "Cloudy ☁️",
-        s if s.starts_with("lightrain") => "Light rain 🌦️",
-        s if s.starts_with("rain") => "Rain 🌧️",
-        s if s.starts_with("heavyrain") => "Heavy rain ⛈️",
-        s if s.starts_with("lightsnow") => "Light snow 🌨️",
-        s if s.starts_with("snow") => "Snow ❄️",
-        s if s.starts_with("heavysnow") => "Heavy snow 🌨️❄️",
-        s if s.starts_with("fog") => "Fog 🌫️",
-        _ => "Unknown",
+/// The larger of two optional values
+fn max_option(a: Option<f64>, b: Option<f64>) -> Option<f64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Builds a forecast entry from a timeseries step and the summary period
+/// (with its length in hours) that describes it
+fn forecast_entry(
+    label: &str,
+    step: &TimeSeries,
+    summary: Option<(&NextHours, u32)>,
+    language: Language,
+) -> ForecastEntry {
+    let details = &step.data.instant.details;
+
+    let symbol_code = summary.map_or("unknown", |(n, _)| n.summary.symbol_code.as_str());
+    let precipitation = summary
+        .and_then(|(n, _)| n.details.as_ref())
+        .and_then(|d| d.precipitation_amount)
+        .unwrap_or(0.0);
+
+    ForecastEntry {
+        label: label.to_string(),
+        time: step.time.clone(),
+        temperature: details.air_temperature,
+        humidity: details.relative_humidity,
+        wind: Wind {
+            speed: details.wind_speed,
+            from_direction: details.wind_from_direction,
+            compass: get_wind_direction(details.wind_from_direction, language),
+        },
+        precipitation: Precipitation {
+            amount: precipitation,
+            period_hours: summary.map_or(1, |(_, hours)| hours),
+        },
+        cloud_cover: details.cloud_area_fraction,
+        pressure: details.air_pressure_at_sea_level,
+        symbol: weather_symbol(symbol_code, language),
+        comfort: comfort(step),
+        details: extended_details(step, summary),
+    }
+}
+
+/// Apparent temperature and related indices at a step
+fn comfort(step: &TimeSeries) -> Comfort {
+    let instant = &step.data.instant.details;
+    Comfort::new(
+        instant.air_temperature,
+        instant.relative_humidity,
+        instant.wind_speed,
+        instant.dew_point_temperature,
+    )
+}
+
+/// Variables of the `complete` product for one step, or `None` for
+/// `compact` data
+fn extended_details(step: &TimeSeries, summary: Option<(&NextHours, u32)>) -> Option<Details> {
+    let instant = &step.data.instant.details;
+    let six_hours = step.data.next_6_hours.as_ref().and_then(|n| n.details.as_ref());
+    let one_hour = step.data.next_1_hours.as_ref().and_then(|n| n.details.as_ref());
+
+    let details = Details {
+        dew_point: instant.dew_point_temperature,
+        fog: instant.fog_area_fraction,
+        cloud_layers: CloudLayers {
+            low: instant.cloud_area_fraction_low,
+            medium: instant.cloud_area_fraction_medium,
+            high: instant.cloud_area_fraction_high,
+        },
+        uv_index: instant.ultraviolet_index_clear_sky,
+        wind_gust: instant.wind_speed_of_gust,
+        temperature_min: six_hours.and_then(|d| d.air_temperature_min),
+        temperature_max: six_hours.and_then(|d| d.air_temperature_max),
+        precipitation_probability: summary
+            .and_then(|(n, _)| n.details.as_ref())
+            .and_then(|d| d.probability_of_precipitation),
+        thunder_probability: one_hour.and_then(|d| d.probability_of_thunder),
+    };
+    (!details.is_empty()).then_some(details)
+}
+
+/// Describes a met.no symbol code; codes outside the symbol table are
+/// passed through as "Unknown"
+fn weather_symbol(code: &str, language: Language) -> Symbol {
+    match WeatherSymbol::parse(code) {
+        Some((symbol, variant)) => Symbol {
+            code: code.to_string(),
+            description: format!("{} {}", language.symbol_name(symbol), symbol.icon(variant)),
+            variant,
+        },
+        None => Symbol {
+            code: code.to_string(),
+            description: language.messages().unknown.to_string(),
+            variant: None,
+        },
     }
 }
 
 /// Converts wind direction in degrees to compass direction
-fn get_wind_direction(degrees: f64) -> &'static str {
-    match degrees as i32 {
-        d if d < 23 || d >= 338 => "North",
-        d if d < 68 => "Northeast",
-        d if d < 113 => "East",
-        d if d < 158 => "Southeast",
-        d if d < 203 => "South",
-        d if d < 248 => "Southwest",
-        d if d < 293 => "West",
-        _ => "Northwest",
+fn get_wind_direction(degrees: f64, language: Language) -> &'static str {
+    let sector = match degrees as i32 {
+        d if !(23..338).contains(&d) => 0,
+        d if d < 68 => 1,
+        d if d < 113 => 2,
+        d if d < 158 => 3,
+        d if d < 203 => 4,
+        d if d < 248 => 5,
+        d if d < 293 => 6,
+        _ => 7,
+    };
+    language.messages().compass[sector]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+
+    /// Canned answer of [`MockMetno`]
+    struct Reply {
+        status: u16,
+        headers: Vec<(&'static str, String)>,
+        body: &'static str,
+    }
+
+    impl Reply {
+        fn new(status: u16, body: &'static str) -> Self {
+            Self { status, headers: Vec::new(), body }
+        }
+
+        fn header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+            self.headers.push((name, value.into()));
+            self
+        }
+    }
+
+    /// A stand-in for met.no on a local port, answering requests with its
+    /// replies in turn and recording the `If-Modified-Since` each carried
+    struct MockMetno {
+        url: String,
+        requests: Arc<Mutex<Vec<Option<String>>>>,
+    }
+
+    impl MockMetno {
+        async fn start(replies: Vec<Reply>) -> Self {
+            let replies = Arc::new(Mutex::new(VecDeque::from(replies)));
+            let requests = Arc::new(Mutex::new(Vec::new()));
+            let recorded = requests.clone();
+            let app = axum::Router::new().fallback(move |headers: axum::http::HeaderMap| {
+                let reply = replies.lock().unwrap().pop_front();
+                let if_modified_since = headers
+                    .get("if-modified-since")
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string);
+                recorded.lock().unwrap().push(if_modified_since);
+                async move {
+                    let reply = reply.unwrap_or_else(|| Reply::new(500, "unexpected request"));
+                    let mut response = axum::http::Response::builder().status(reply.status);
+                    for (name, value) in reply.headers {
+                        response = response.header(name, value);
+                    }
+                    response.body(axum::body::Body::from(reply.body)).unwrap()
+                }
+            });
+
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            tokio::spawn(async move { axum::serve(listener, app).await });
+            Self { url, requests }
+        }
+
+        fn client(&self, max_retries: u32) -> WeatherClient {
+            WeatherClient::builder()
+                .base_url(&self.url)
+                .contact("tests@example.com")
+                .max_retries(max_retries)
+                .build()
+                .unwrap()
+        }
+
+        /// A product URL on the mock
+        fn product(&self) -> String {
+            format!("{}/{}?lat=59.9100&lon=10.7500", self.url, COMPACT_PATH)
+        }
+
+        fn requests(&self) -> Vec<Option<String>> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    fn http_date(from_now: i64) -> String {
+        let now = SystemTime::now();
+        let offset = Duration::from_secs(from_now.unsigned_abs());
+        httpdate::fmt_http_date(if from_now < 0 { now - offset } else { now + offset })
+    }
+
+    const LAST_MODIFIED_AT: &str = "Sat, 04 May 2024 10:00:00 GMT";
+
+    #[tokio::test]
+    async fn serves_fresh_responses_from_the_cache() {
+        let mock = MockMetno::start(vec![Reply::new(200, r#"{"a": 1}"#).header("expires", http_date(3600))]).await;
+        let client = mock.client(0);
+
+        for _ in 0..2 {
+            assert_eq!(client.fetch::<Value>(&mock.product()).await.unwrap(), json!({"a": 1}));
+        }
+        assert_eq!(mock.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn revalidates_expired_responses_with_if_modified_since() {
+        let mock = MockMetno::start(vec![
+            // Without Expires the entry is only good for revalidation
+            Reply::new(200, r#"{"a": 1}"#).header("last-modified", LAST_MODIFIED_AT),
+            Reply::new(304, "").header("expires", http_date(3600)),
+        ])
+        .await;
+        let client = mock.client(0);
+
+        for _ in 0..3 {
+            assert_eq!(client.fetch::<Value>(&mock.product()).await.unwrap(), json!({"a": 1}));
+        }
+        // The 304 renewed the entry, so the third lookup stayed local
+        assert_eq!(mock.requests(), [None, Some(LAST_MODIFIED_AT.to_string())]);
+    }
+
+    #[tokio::test]
+    async fn refetches_responses_whose_expires_has_passed() {
+        let mock = MockMetno::start(vec![
+            Reply::new(200, r#"{"a": 1}"#).header("expires", http_date(-60)),
+            Reply::new(200, r#"{"a": 2}"#).header("expires", http_date(3600)),
+        ])
+        .await;
+        let client = mock.client(0);
+
+        assert_eq!(client.fetch::<Value>(&mock.product()).await.unwrap(), json!({"a": 1}));
+        assert_eq!(client.fetch::<Value>(&mock.product()).await.unwrap(), json!({"a": 2}));
+        assert_eq!(mock.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn accepts_deprecated_products_and_notes_them_once() {
+        let mock = MockMetno::start(vec![Reply::new(203, r#"{"a": 1}"#)]).await;
+        let client = mock.client(0);
+
+        assert!(!client.deprecation_warned.load(Ordering::Relaxed));
+        assert_eq!(client.fetch::<Value>(&mock.product()).await.unwrap(), json!({"a": 1}));
+        assert!(client.deprecation_warned.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn reports_unreadable_bodies_as_parse_errors() {
+        let mock = MockMetno::start(vec![Reply::new(200, "<html>")]).await;
+        let error = mock.client(0).fetch::<Value>(&mock.product()).await.unwrap_err();
+        assert!(matches!(error, WeatherError::Parse(_)), "{:?}", error);
+    }
+
+    #[test]
+    fn identifies_the_application_and_its_operator() {
+        let builder = WeatherClient::builder();
+        assert_eq!(builder.user_agent_header(), USER_AGENT_PRODUCT);
+        assert!(USER_AGENT_PRODUCT.starts_with("yr-weather-mcp/"));
+
+        let builder = builder.contact("ops@example.com");
+        assert_eq!(builder.user_agent_header(), format!("{} ops@example.com", USER_AGENT_PRODUCT));
+
+        let builder = builder.user_agent("custom/1.0 ops@example.com");
+        assert_eq!(builder.user_agent_header(), "custom/1.0 ops@example.com");
+    }
+
+    #[test]
+    fn accepts_only_http_base_urls() {
+        for base_url in ["ftp://api.met.no/weatherapi", "api.met.no/weatherapi", "file:///tmp", ""] {
+            let error = WeatherClient::builder().base_url(base_url).build().err();
+            assert!(matches!(error, Some(WeatherError::InvalidBaseUrl(_))), "{:?}", base_url);
+        }
+
+        let client = WeatherClient::builder().base_url("http://localhost:9000/").build().unwrap();
+        assert_eq!(client.base_url, "http://localhost:9000");
+        assert!(WeatherClient::builder().base_url("https://api.met.no/weatherapi").build().is_ok());
+    }
+
+    fn retry_after_value(value: &str) -> Option<Duration> {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, value.parse().unwrap());
+        retry_after(&headers)
+    }
+
+    #[test]
+    fn reads_retry_after_in_seconds_and_as_a_date() {
+        assert_eq!(retry_after_value("3"), Some(Duration::from_secs(3)));
+        assert_eq!(retry_after_value(" 120 "), Some(Duration::from_secs(120)));
+
+        let wait = retry_after_value(&http_date(60)).unwrap();
+        assert!(wait > Duration::from_secs(55) && wait <= Duration::from_secs(60), "{:?}", wait);
+        // A date already past means no wait at all
+        assert_eq!(retry_after_value(&http_date(-60)), Some(Duration::ZERO));
+
+        assert_eq!(retry_after_value("soon"), None);
+        assert_eq!(retry_after(&HeaderMap::new()), None);
+    }
+
+    #[tokio::test]
+    async fn waits_out_a_short_retry_after() {
+        let mock = MockMetno::start(vec![
+            Reply::new(429, "").header("retry-after", "1"),
+            Reply::new(200, r#"{"a": 1}"#),
+        ])
+        .await;
+        let client = mock.client(0);
+        let start = std::time::Instant::now();
+
+        assert_eq!(client.fetch::<Value>(&mock.product()).await.unwrap(), json!({"a": 1}));
+        assert!(start.elapsed() >= Duration::from_secs(1));
+        assert_eq!(mock.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_repeated_rate_limiting() {
+        let mock = MockMetno::start(vec![
+            Reply::new(429, "").header("retry-after", "0"),
+            Reply::new(429, "").header("retry-after", "0"),
+            Reply::new(200, r#"{"a": 1}"#),
+        ])
+        .await;
+
+        let error = mock.client(2).fetch::<Value>(&mock.product()).await.unwrap_err();
+        assert!(matches!(error, WeatherError::RateLimited(Some(_))), "{:?}", error);
+        assert_eq!(mock.requests().len(), MAX_RATE_LIMIT_ATTEMPTS as usize);
+    }
+
+    #[tokio::test]
+    async fn fails_fast_on_a_long_retry_after_and_holds_back_other_calls() {
+        let mock = MockMetno::start(vec![Reply::new(429, "").header("retry-after", "60")]).await;
+        let client = mock.client(0);
+        let start = std::time::Instant::now();
+
+        let error = client.fetch::<Value>(&mock.product()).await.unwrap_err();
+        assert!(matches!(error, WeatherError::RateLimited(Some(wait)) if wait > MAX_RATE_LIMIT_WAIT), "{:?}", error);
+        // Later calls hit the pause without sending anything
+        let error = client.fetch::<Value>(&mock.product()).await.unwrap_err();
+        assert!(matches!(error, WeatherError::RateLimited(Some(_))), "{:?}", error);
+        assert!(start.elapsed() < MAX_RATE_LIMIT_WAIT);
+        assert_eq!(mock.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn backs_off_by_default_without_retry_after() {
+        let mock = MockMetno::start(vec![Reply::new(429, "")]).await;
+
+        let error = mock.client(0).fetch::<Value>(&mock.product()).await.unwrap_err();
+        assert!(
+            matches!(error, WeatherError::RateLimited(Some(wait)) if wait > DEFAULT_RATE_LIMIT_BACKOFF - Duration::from_secs(1)),
+            "{:?}",
+            error
+        );
+        assert_eq!(mock.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn retries_transient_statuses() {
+        for status in [502, 503, 504] {
+            let mock = MockMetno::start(vec![Reply::new(status, ""), Reply::new(200, r#"{"a": 1}"#)]).await;
+            assert_eq!(mock.client(1).fetch::<Value>(&mock.product()).await.unwrap(), json!({"a": 1}));
+            assert_eq!(mock.requests().len(), 2, "HTTP {}", status);
+        }
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_the_configured_retries() {
+        let mock = MockMetno::start(vec![Reply::new(503, ""), Reply::new(503, ""), Reply::new(503, "")]).await;
+
+        let error = mock.client(2).fetch::<Value>(&mock.product()).await.unwrap_err();
+        assert!(matches!(error, WeatherError::Upstream(503)), "{:?}", error);
+        assert_eq!(mock.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_other_failures() {
+        for (status, expected) in [(500, WeatherError::Upstream(500)), (400, WeatherError::BadRequest(400)), (404, WeatherError::Rejected(404))] {
+            let mock = MockMetno::start(vec![Reply::new(status, ""), Reply::new(200, "{}")]).await;
+            let error = mock.client(2).fetch::<Value>(&mock.product()).await.unwrap_err();
+            assert_eq!(error.to_string(), expected.to_string());
+            assert_eq!(mock.requests().len(), 1, "HTTP {}", status);
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_refused_connections_but_not_timeouts() {
+        // Nothing listens on the port once the listener is dropped
+        let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+        let error = reqwest::get(format!("http://{}", closed)).await.unwrap_err();
+        assert!(is_transient_error(&error), "{:?}", error);
+
+        // Accepts connections and never answers
+        let stalled = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = stalled.local_addr().unwrap();
+        let accepted = Arc::new(Mutex::new(Vec::new()));
+        let connections = accepted.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = stalled.accept().await {
+                connections.lock().unwrap().push(stream);
+            }
+        });
+        let client = WeatherClient::builder()
+            .base_url(format!("http://{}", address))
+            .request_timeout(Duration::from_millis(200))
+            .max_retries(2)
+            .build()
+            .unwrap();
+
+        let error = client.fetch::<Value>(&format!("http://{}/", address)).await.unwrap_err();
+        let WeatherError::Network(error) = error else {
+            panic!("expected a network error, got {:?}", error);
+        };
+        assert!(error.is_timeout() && !is_transient_error(&error), "{:?}", error);
+        assert_eq!(accepted.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn retry_delays_grow_with_jitter_up_to_the_cap() {
+        for retry in 1..=8 {
+            let full = RETRY_BASE_DELAY.saturating_mul(1 << (retry - 1)).min(MAX_RETRY_DELAY);
+            for _ in 0..50 {
+                let delay = WeatherClient::retry_delay(retry);
+                assert!(delay >= full / 2 && delay <= full, "retry {}: {:?}", retry, delay);
+            }
+        }
+        assert!(WeatherClient::retry_delay(1) <= Duration::from_millis(500));
+        assert!(WeatherClient::retry_delay(u32::MAX) <= MAX_RETRY_DELAY);
+    }
+
+    /// A timeseries step at `time` (Unix seconds) with optional 1-hour and
+    /// 6-hour summaries given as symbol and precipitation
+    fn step(time: i64, temperature: f64, next_1: Option<(&str, f64)>, next_6: Option<(&str, f64)>) -> Value {
+        let summary = |(symbol, precipitation): (&str, f64)| {
+            json!({"summary": {"symbol_code": symbol}, "details": {"precipitation_amount": precipitation}})
+        };
+        json!({
+            "time": timezone::format_utc(time),
+            "data": {
+                "instant": {"details": {
+                    "air_temperature": temperature,
+                    "wind_speed": 3.0,
+                    "relative_humidity": 70.0,
+                    "air_pressure_at_sea_level": 1013.0,
+                    "cloud_area_fraction": 50.0,
+                    "wind_from_direction": 180.0
+                }},
+                "next_1_hours": next_1.map(summary),
+                "next_6_hours": next_6.map(summary)
+            }
+        })
+    }
+
+    fn forecast(steps: Vec<Value>) -> WeatherResponse {
+        serde_json::from_value(json!({"properties": {"timeseries": steps}})).unwrap()
+    }
+
+    /// Shaped like locationforecast: `hourly` steps with both summaries,
+    /// then `six_hourly` steps 6 hours apart with only the 6-hour one
+    fn series(start: &str, hourly: i64, six_hourly: i64) -> WeatherResponse {
+        let start = utc(start);
+        let hourly_steps = (0..hourly).map(|h| step(start + h * 3600, 10.0, Some(("rain", 0.5)), Some(("rain", 3.0))));
+        let last_hourly = start + (hourly - 1) * 3600;
+        let six_hourly_steps =
+            (1..=six_hourly).map(|n| step(last_hourly + n * 6 * 3600, 10.0, None, Some(("cloudy", 2.0))));
+        forecast(hourly_steps.chain(six_hourly_steps).collect())
+    }
+
+    fn utc(time: &str) -> i64 {
+        timezone::parse_utc(time).unwrap()
+    }
+
+    fn stats(samples: &[Sample], tz: &TimeZone) -> PeriodStats {
+        period_stats(&samples.iter().collect::<Vec<_>>(), tz, Language::En).unwrap()
+    }
+
+    #[test]
+    fn samples_switch_from_hourly_to_six_hourly_periods() {
+        let response = series("2024-05-04T00:00:00Z", 43, 4);
+        let samples = samples(&response).unwrap();
+
+        let hours: Vec<u32> = samples.iter().map(|s| s.hours).collect();
+        assert_eq!(hours[..42], [1; 42]);
+        // The last hourly step is followed by a 6-hour gap, so its 6-hour
+        // summary covers it; the hourly one would leave 5 hours uncounted
+        assert_eq!(hours[42..], [6; 5]);
+
+        // Each hour is counted once: 42 × 0.5 mm, then 3 mm for the 6 hours
+        // from the last hourly step and 4 × 2 mm
+        let stats = stats(&samples, &TimeZone::utc());
+        assert_eq!(stats.hours, 42 + 5 * 6);
+        assert!((stats.precipitation - (21.0 + 3.0 + 8.0)).abs() < 1e-9, "{}", stats.precipitation);
+    }
+
+    #[test]
+    fn samples_reject_unreadable_times() {
+        let mut step = step(0, 10.0, None, None);
+        step["time"] = json!("yesterday");
+        assert!(matches!(samples(&forecast(vec![step])), Err(WeatherError::Parse(_))));
+    }
+
+    #[test]
+    fn period_stats_cover_extremes_and_prefer_daytime_weather() {
+        let start = utc("2024-05-04T00:00:00Z");
+        // Six clear night hours, then two rainy morning hours
+        let mut steps: Vec<Value> = (0..6)
+            .map(|h| step(start + h * 3600, 8.0 - h as f64, Some(("clearsky_night", 0.0)), None))
+            .collect();
+        steps.push(step(start + 6 * 3600, 9.0, Some(("rain", 1.5)), None));
+        steps.push(step(start + 7 * 3600, 11.0, Some(("rain", 0.5)), None));
+        steps[7]["data"]["instant"]["details"]["wind_speed"] = json!(9.0);
+        steps[7]["data"]["instant"]["details"]["wind_speed_of_gust"] = json!(14.0);
+        steps[1]["data"]["next_1_hours"]["details"]["probability_of_precipitation"] = json!(40.0);
+        let response = forecast(steps);
+
+        let stats = stats(&samples(&response).unwrap(), &TimeZone::utc());
+        assert_eq!((stats.temperature_min, stats.temperature_max), (3.0, 11.0));
+        assert_eq!(stats.precipitation, 2.0);
+        assert_eq!(stats.symbol.code, "rain");
+        assert_eq!(stats.wind_speed_max, 9.0);
+        assert_eq!(stats.wind_gust_max, Some(14.0));
+        assert_eq!(stats.precipitation_probability_max, Some(40.0));
+        assert_eq!(stats.hours, 8);
+        assert!(period_stats(&[], &TimeZone::utc(), Language::En).is_none());
+    }
+
+    #[test]
+    fn period_stats_include_six_hour_extremes() {
+        let mut steps = vec![step(0, 10.0, None, Some(("cloudy", 1.0))), step(6 * 3600, 12.0, None, Some(("cloudy", 1.0)))];
+        steps[0]["data"]["next_6_hours"]["details"]["air_temperature_min"] = json!(7.5);
+        steps[1]["data"]["next_6_hours"]["details"]["air_temperature_max"] = json!(15.0);
+        let response = forecast(steps);
+
+        let stats = stats(&samples(&response).unwrap(), &TimeZone::utc());
+        assert_eq!((stats.temperature_min, stats.temperature_max), (7.5, 15.0));
+        // Night-only periods fall back to the most common symbol overall
+        assert_eq!(stats.symbol.code, "cloudy");
+    }
+
+    #[test]
+    fn weekly_days_follow_local_calendar_days() {
+        // Seoul is UTC+9, so 6-hourly steps at 18:00 UTC fall on the next
+        // local day
+        let seoul = TimeZone::from_name("Asia/Seoul").unwrap();
+        let days = weekly_days(&series("2024-05-04T00:00:00Z", 1, 11), &seoul, Language::En).unwrap();
+
+        let dates: Vec<&str> = days.iter().map(|d| d.date.as_str()).collect();
+        assert_eq!(dates, ["2024-05-04", "2024-05-05", "2024-05-06", "2024-05-07"]);
+        let hours: Vec<u32> = days.iter().map(|d| d.stats.hours).collect();
+        // 09:00 and 15:00 on the first day; 03:00, 09:00, 15:00 and 21:00
+        // on full days; the last step at 03:00
+        assert_eq!(hours, [6 + 6 + 6, 24, 24, 6]);
+        let labels: Vec<&str> = days.iter().map(|d| d.label.as_str()).collect();
+        assert_eq!(labels, ["Today", "Tomorrow", "Mon 6 May", "Tue 7 May"]);
+    }
+
+    #[test]
+    fn weekly_days_span_daylight_saving_changes() {
+        // Summer time in Oslo ended 2024-10-27 at 01:00 UTC, making that
+        // local day 25 hours long
+        let oslo = TimeZone::from_name("Europe/Oslo").unwrap();
+        let days = weekly_days(&series("2024-10-26T00:00:00Z", 72, 0), &oslo, Language::En).unwrap();
+
+        let summary: Vec<(&str, u32)> = days.iter().map(|d| (d.date.as_str(), d.stats.hours)).collect();
+        // The final step stands for the 6 hours its summary covers
+        assert_eq!(summary, [("2024-10-26", 22), ("2024-10-27", 25), ("2024-10-28", 24), ("2024-10-29", 6)]);
+    }
+
+    #[test]
+    fn weekly_days_stop_after_a_week() {
+        let days = weekly_days(&series("2024-05-04T00:00:00Z", 1, 40), &TimeZone::utc(), Language::En).unwrap();
+        assert_eq!(days.len(), 7);
+        assert_eq!(days[6].date, "2024-05-10");
+        assert!(weekly_days(&forecast(Vec::new()), &TimeZone::utc(), Language::En).is_err());
+    }
+
+    /// Hourly steps from `start` whose temperature is the local hour in `tz`
+    fn hourly_by_local_hour(start: &str, hours: i64, tz: &TimeZone) -> WeatherResponse {
+        let start = utc(start);
+        let steps = (0..hours).map(|h| {
+            let time = start + h * 3600;
+            step(time, f64::from(tz.local(time).hour()), Some(("rain", 0.5)), Some(("rain", 3.0)))
+        });
+        forecast(steps.collect())
+    }
+
+    #[test]
+    fn tomorrow_day_is_split_into_local_day_parts() {
+        let seoul = TimeZone::from_name("Asia/Seoul").unwrap();
+        // From 09:00 on 4 May to 08:00 on 6 May, Seoul time
+        let day = tomorrow_day(&hourly_by_local_hour("2024-05-04T00:00:00Z", 48, &seoul), &seoul, Language::En).unwrap();
+
+        assert_eq!((day.label.as_str(), day.date.as_str()), ("Tomorrow", "2024-05-05"));
+        assert_eq!((day.stats.temperature_min, day.stats.temperature_max), (0.0, 23.0));
+        assert_eq!((day.stats.hours, day.stats.precipitation), (24, 12.0));
+
+        let periods: Vec<_> = day
+            .periods
+            .iter()
+            .map(|p| (p.part, p.start.as_str(), p.end.as_str(), p.stats.temperature_min, p.stats.temperature_max))
+            .collect();
+        assert_eq!(
+            periods,
+            [
+                (DayPart::Night, "2024-05-04T15:00:00Z", "2024-05-04T21:00:00Z", 0.0, 5.0),
+                (DayPart::Morning, "2024-05-04T21:00:00Z", "2024-05-05T03:00:00Z", 6.0, 11.0),
+                (DayPart::Afternoon, "2024-05-05T03:00:00Z", "2024-05-05T09:00:00Z", 12.0, 17.0),
+                (DayPart::Evening, "2024-05-05T09:00:00Z", "2024-05-05T15:00:00Z", 18.0, 23.0),
+            ]
+        );
+        assert!(day.periods.iter().all(|p| p.stats.hours == 6 && p.stats.precipitation == 3.0));
+        assert_eq!(day.periods[1].label, "Morning");
+    }
+
+    #[test]
+    fn tomorrow_day_leaves_out_parts_without_data() {
+        let utc_zone = TimeZone::utc();
+        // Ends at 13:00 tomorrow
+        let day = tomorrow_day(&hourly_by_local_hour("2024-05-04T12:00:00Z", 26, &utc_zone), &utc_zone, Language::En).unwrap();
+
+        let parts: Vec<DayPart> = day.periods.iter().map(|p| p.part).collect();
+        assert_eq!(parts, [DayPart::Night, DayPart::Morning, DayPart::Afternoon]);
+        let afternoon = &day.periods[2].stats;
+        assert_eq!((afternoon.temperature_min, afternoon.temperature_max), (12.0, 13.0));
+
+        let today_only = hourly_by_local_hour("2024-05-04T12:00:00Z", 6, &utc_zone);
+        assert!(matches!(tomorrow_day(&today_only, &utc_zone, Language::En), Err(WeatherError::NoData(_))));
+    }
+
+    #[test]
+    fn tomorrow_day_handles_a_25_hour_day() {
+        let oslo = TimeZone::from_name("Europe/Oslo").unwrap();
+        let day = tomorrow_day(&hourly_by_local_hour("2024-10-26T00:00:00Z", 72, &oslo), &oslo, Language::Nb).unwrap();
+
+        assert_eq!((day.label.as_str(), day.date.as_str()), ("I morgen", "2024-10-27"));
+        assert_eq!(day.stats.hours, 25);
+        // The repeated 02:00 hour falls in the night
+        assert_eq!(day.periods[0].stats.hours, 7);
+        assert_eq!(day.periods[0].start, "2024-10-26T22:00:00Z");
+        assert_eq!(day.periods[0].end, "2024-10-27T05:00:00Z");
+        assert_eq!(day.periods[0].label, "Natt");
     }
 }